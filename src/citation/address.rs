@@ -4,61 +4,180 @@
 //! This code breings together the book linkings and the citation to create a formated ciatation struct
 //! 
 
-use std::collections::HashSet;
-use regex::Regex;
+use serde::Serialize;
 
-pub mod book_linking;
-// mod roman_numerals;
-#[path = "./roman_numerals/lib.rs"] mod roman_numerals;
+use super::{book_linking, parser};
+use super::book_id::BookId;
+use super::versification::Scheme;
+use crate::span::Span;
 
 #[derive(Debug)]
-#[derive(Clone)]
-/// A struct for the organization of a scripture citation: book, start chapter, start verse, end chapter end verse.
+#[derive(Clone, PartialEq, Serialize)]
+/// A struct for the organization of a scripture citation: book, start chapter, start verse, end chapter end verse,
+/// and the versification scheme the numbers are in. The span points back to the raw match in the text, 
+/// when the citation was extracted from one. Inferred citations did not name their book, and had it 
+/// filled in from the text around them. Citations written with `f.` or `ff.` after their verse keep 
+/// that form for display. The start and end verse can be narrowed down to a part of the verse, like `21a`.
 pub struct ScriptureCitation {
-    book: String, 
-    start_chap: Option<i16>,
-    start_verse: Option<i16>,
-    end_chap: Option<i16>,
-    end_verse: Option<i16>,
+    pub book: BookId, 
+    pub start_chap: Option<i16>,
+    pub start_verse: Option<i16>,
+    pub start_part: Option<VersePart>,
+    pub end_chap: Option<i16>,
+    pub end_verse: Option<i16>,
+    pub end_part: Option<VersePart>,
+    pub scheme: Scheme,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub span: Option<Span>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub inferred: bool,
+    pub following: Option<Following>,
+}
+
+/// A part of a verse, like the `b` of `4b`, with an optional Greek sub-part, like the `α` of `12aα`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct VersePart {
+    /// A letter from `a` to `e`
+    pub part: char,
+    /// A Greek letter from `α` to `δ`
+    pub sub_part: Option<char>,
+}
+
+impl std::fmt::Display for VersePart {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.part)?;
+        if let Some(sub_part) = self.sub_part {
+            write!(f, "{}", sub_part)?;
+        }
+        Ok(())
+    }
+}
+
+impl Serialize for VersePart {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+/// The `f.` and `ff.` written after a verse, for the verses that follow it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum Following {
+    /// `f.`, the verse and the one after it
+    #[serde(rename = "f")]
+    Verse,
+    /// `ff.`, the verse and the rest of the passage, up to the end of the chapter
+    #[serde(rename = "ff")]
+    Verses,
+}
+
+impl std::fmt::Display for Following {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Following::Verse => write!(f, "f."),
+            Following::Verses => write!(f, "ff."),
+        }
+    }
 }
 
 /// This is the struct for the whole citation list. 
 /// This struct will cover even 1 Cor. 4:3, 5, 6-7; 5:1-4
 pub struct CitationList {
-    book: Option<String>,
-    ranges: HashSet<String>,
-    dividers: HashSet<String>,
-    // additions: HashSet<String>,
-    curr_citation: Option<ScriptureCitation>,
     pub scrip_vec: Vec<ScriptureCitation>,
+    /// The versification scheme of the text the citations come from
+    pub scheme: Scheme,
 
 }
 
-/// This enum lists the diferent options a verse part can have.
-enum CitationParts {
-    StartChap,
-    // StartVerse,
-    Verse,
-    EndChap,
-    // EndVerse,
+/// The reasons a citation can fall outside of the versification of its book.
+#[derive(Debug, Clone, PartialEq)]
+pub enum RangeError {
+    /// The chapter is zero, or past the last chapter of the book
+    Chapter { chapter: i16, chapters: i16 },
+    /// The verse is zero, or past the last verse of the chapter
+    Verse { chapter: i16, verse: i16, verses: i16 },
+    /// The end of the range comes before its start
+    Reversed,
+}
+
+impl std::fmt::Display for RangeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            RangeError::Chapter { chapter, chapters } => 
+                write!(f, "chapter {} is out of range, the book has {} chapters", chapter, chapters),
+            RangeError::Verse { chapter, verse, verses } => 
+                write!(f, "verse {} is out of range, chapter {} has {} verses", verse, chapter, verses),
+            RangeError::Reversed => write!(f, "the range ends before it starts"),
+        }
+    }
+}
 
+/// The problems that can come up while reading a citation. None of them stop the rest of the 
+/// citation from being read, they are returned by `CitationList::insert` so that the caller can 
+/// decide how to report them.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Diagnostic {
+    /// No book in the library matches the name
+    UnknownBook(String),
+    /// More than one book matches the name, and the chosen one was used
+    AmbiguousBook { name: String, chosen: BookId, candidates: Vec<BookId> },
+    /// Part of the chapter and verse address could not be read
+    MalformedAddress { citation: String, element: String },
+    /// The citation falls outside of the versification of its book, and was dropped
+    ImpossibleRange { citation: ScriptureCitation, reason: RangeError },
+}
+
+impl std::fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Diagnostic::UnknownBook(name) => write!(f, "Unknown book: {}", name),
+            Diagnostic::AmbiguousBook { name, chosen, candidates } => {
+                let all: Vec<&str> = candidates.iter().map(|c| c.name()).collect();
+                let others: Vec<&str> = candidates.iter().filter(|c| *c != chosen).map(|c| c.name()).collect();
+                write!(f, "{} could be {}, using {}. Did you mean: {}?", name, all.join(", "), chosen, others.join(" or "))
+            },
+            Diagnostic::MalformedAddress { citation, element } => 
+                write!(f, "Error in Formating Citation: {} (could not read `{}`)", citation, element),
+            Diagnostic::ImpossibleRange { citation, reason } => 
+                write!(f, "Impossible Citation: {} ({})", citation, reason),
+        }
+    }
+}
+
+/// Reads one OSIS id of a reference, like `1Cor.4.6`, into the canonical book, chapter 
+/// and verse.
+fn read_osis_id(osis: &str, osis_id: &str, library: &book_linking::Library) -> Result<(BookId, Option<i16>, Option<i16>), Diagnostic> {
+    let malformed = || Diagnostic::MalformedAddress { citation: String::from(osis), element: String::from(osis_id) };
+    let mut parts = osis_id.trim().split('.');
+    let book_id = parts.next().unwrap_or("");
+    let book = library.osis_book(book_id).ok_or_else(|| Diagnostic::UnknownBook(String::from(book_id)))?;
+    let numbers = parts.map(|part| part.parse::<i16>().map_err(|_| malformed()))
+        .collect::<Result<Vec<i16>, Diagnostic>>()?;
+    match numbers.as_slice() {
+        [] => Ok((book, None, None)),
+        [chapter] => Ok((book, Some(*chapter), None)),
+        [chapter, verse] => Ok((book, Some(*chapter), Some(*verse))),
+        _ => Err(malformed()),
+    }
 }
 
 impl std::fmt::Display for ScriptureCitation {
     /// Formats the Scripture citation into a pretty printed string
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        let mut printstring = self.book.clone();
-        if !self.start_chap.is_none() {
-            printstring = format!("{} {}", printstring, self.start_chap.unwrap());
+        let mut printstring = self.book.to_string();
+        if let Some(start_chap) = self.start_chap {
+            printstring = format!("{} {}", printstring, start_chap);
+        }
+        if let Some(start_verse) = self.start_verse {
+            printstring = format!("{}: {}{}", printstring, start_verse, part(self.start_part));
         }
-        if !self.start_verse.is_none() {
-            printstring = format!("{}: {}", printstring, self.start_verse.unwrap());
+        if let Some(following) = self.following {
+            return write!(f, "{}{}", printstring, following)
         }
-        if !self.end_chap.is_none() {
-            printstring = format!("{} - {}", printstring, self.end_chap.unwrap());
+        if let Some(end_chap) = self.end_chap {
+            printstring = format!("{} - {}", printstring, end_chap);
         }
-        if !self.end_verse.is_none() {
-            printstring = format!("{}: {}", printstring, self.end_verse.unwrap());
+        if let Some(end_verse) = self.end_verse {
+            printstring = format!("{}: {}{}", printstring, end_verse, part(self.end_part));
         }
         write!(f, "{}", printstring)
     }
@@ -67,132 +186,262 @@ impl std::fmt::Display for ScriptureCitation {
 
 impl ScriptureCitation {
     /// Initiate a new Scripture Citation Struct
-    pub fn new(name: &String, start_chap: Option<i16>) -> ScriptureCitation {
+    pub fn new(book: BookId, start_chap: Option<i16>) -> ScriptureCitation {
         ScriptureCitation { 
-            book: name.to_owned(), 
-            start_chap: start_chap,
+            book, 
+            start_chap,
             start_verse: None,
+            start_part: None,
             end_chap: None,
             end_verse: None,
+            end_part: None,
+            scheme: Scheme::Kjv,
+            span: None,
+            inferred: false,
+            following: None,
         }
     }
 
-}
+    /// The compact canonical form of the citation, as used in a TEI `cRef`. Ranges
+    /// within one chapter only repeat the verse.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scripcit::{BookId, ScriptureCitation};
+    ///
+    /// let mut citation = ScriptureCitation::new(BookId::SecondSamuel, Some(4));
+    /// citation.start_verse = Some(3);
+    /// citation.end_chap = Some(4);
+    /// citation.end_verse = Some(4);
+    /// assert_eq!(citation.cref(), "2 Samuel 4:3-4");
+    /// ```
+    pub fn cref(&self) -> String {
+        self.compact(self.book.name())
+    }
 
-impl CitationList {
-    /// Returns the type of Address element that was previous to the current element
-    fn chapter_previous(&mut self, element: &String) -> Address  {
-        if self.ranges.contains(element) {
-            let prev_element = Address::ChapterRange;
-            return prev_element
+    /// The USFM reference of the citation, like `1CO 4:6-7`, as used in `\xt` cross 
+    /// references, or None if the book has no USFM code in the library.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scripcit::{CitationList, Library};
+    ///
+    /// let library = Library::create().unwrap();
+    /// let mut scriptures = CitationList::new();
+    /// scriptures.insert("1 Cor. iv. 6-7", &library);
+    /// assert_eq!(scriptures.scrip_vec[0].to_usfm(&library).unwrap(), "1CO 4:6-7");
+    /// ```
+    pub fn to_usfm(&self, library: &book_linking::Library) -> Option<String> {
+        library.usfm_id(self.book).map(|code| self.compact(code))
+    }
+
+    /// The citation in the compact form, after the given name of its book
+    fn compact(&self, book: &str) -> String {
+        let mut compact = String::from(book);
+        if let Some(start_chap) = self.start_chap {
+            compact = format!("{} {}", compact, start_chap);
         }
-        if self.dividers.len() == 3 && !self.ranges.contains(element){
-            let mut temp_set: HashSet<String> = HashSet::new();
-            temp_set.insert(element.to_owned());
-            self.dividers = temp_set;
-            let prev_element = Address::Divider;
-            return prev_element
+        if let Some(start_verse) = self.start_verse {
+            compact = format!("{}:{}{}", compact, start_verse, part(self.start_part));
         }
-        if self.dividers.len() == 1 && !self.dividers.contains(element) {
-            let prev_element = Address::Addition;
-            return prev_element
+        if let Some(following) = self.following {
+            return format!("{}{}", compact, following)
         }
-        if self.dividers.contains(element) {
-            let prev_element = Address::Divider;
-            return prev_element
+        let end_part = part(self.end_part);
+        match (self.end_chap, self.end_verse) {
+            (Some(end_chap), Some(end_verse)) if Some(end_chap) == self.start_chap && self.start_verse.is_some() => 
+                compact = format!("{}-{}{}", compact, end_verse, end_part),
+            (Some(end_chap), Some(end_verse)) => compact = format!("{}-{}:{}{}", compact, end_chap, end_verse, end_part),
+            (Some(end_chap), None) => compact = format!("{}-{}", compact, end_chap),
+            (None, Some(end_verse)) => compact = format!("{}-{}{}", compact, end_verse, end_part),
+            (None, None) => (),
         }
-        Address::Error
+        compact
     }
 
-
-    /// Updates the current citation based on the current place in the element. 
-    /// The purpose of this function is to read the parts of the citation in order, and make 
-    /// smart decisions about what could the next possible option be. Both looking forward 
-    /// And looking backward. 
-    /// 
-    fn update_curr_citation(&mut self, citation_part: CitationParts, element: &String) {
-        let mut citation = self.curr_citation.clone().unwrap_or(ScriptureCitation::new(&self.book.clone().unwrap(), None));
-       
-        let num = convert_str_to_address_num(element);
-
-        match citation_part {
-            CitationParts::StartChap => { citation.start_chap = num; }, 
-            // CitationParts::StartVerse => {citation.start_verse = num; }, 
-            CitationParts::Verse => {
-                if citation.start_verse.is_none() { 
-                    citation.start_verse = num 
-                } else { 
-                    citation.end_chap = citation.start_chap.clone();
-                    citation.end_verse = num;
-                };},
-            CitationParts::EndChap => { citation.end_chap = num; }
+    /// The OSIS reference of the citation, like `1Cor.4.6-1Cor.4.7`, or None if the 
+    /// book has no OSIS abbreviation in the library. Verse parts are written as grains, 
+    /// like `Rom.3.21!a`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scripcit::{CitationList, Library};
+    ///
+    /// let library = Library::create().unwrap();
+    /// let mut scriptures = CitationList::new();
+    /// scriptures.insert("1 Cor. iv. 6-7", &library);
+    /// assert_eq!(scriptures.scrip_vec[0].to_osis(&library).unwrap(), "1Cor.4.6-1Cor.4.7");
+    /// ```
+    pub fn to_osis(&self, library: &book_linking::Library) -> Option<String> {
+        let book = library.osis_id(self.book)?;
+        let mut osis = String::from(book);
+        if let Some(start_chap) = self.start_chap {
+            osis = format!("{}.{}", osis, start_chap);
+            if let Some(start_verse) = self.start_verse {
+                osis = format!("{}.{}{}", osis, start_verse, grain(self.start_part));
+            }
+        }
+        match (self.end_chap.or(self.start_chap), self.end_verse) {
+            (Some(end_chap), Some(end_verse)) => osis = format!("{}-{}.{}.{}{}", osis, book, end_chap, end_verse, grain(self.end_part)),
+            (Some(end_chap), None) if self.end_chap.is_some() => osis = format!("{}-{}.{}", osis, book, end_chap),
+            _ => (),
         }
+        Some(osis)
+    }
 
-        self.curr_citation = Some(citation);
+    /// Reads an OSIS reference, like `1Cor.4.6-1Cor.4.7`, back into a citation. The 
+    /// range has to stay within one book.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scripcit::{Library, ScriptureCitation};
+    ///
+    /// let library = Library::create().unwrap();
+    /// let citation = ScriptureCitation::from_osis("1Cor.4.6-1Cor.4.7", &library).unwrap();
+    /// assert_eq!(citation.to_string(), "1 Corinthians 4: 6 - 4: 7");
+    /// ```
+    pub fn from_osis(osis: &str, library: &book_linking::Library) -> Result<ScriptureCitation, Diagnostic> {
+        let mut parts = osis.trim().splitn(2, '-');
+        let (book, start_chap, start_verse) = read_osis_id(osis, parts.next().unwrap_or(""), library)?;
+        let mut citation = ScriptureCitation::new(book, start_chap);
+        citation.start_verse = start_verse;
+        if let Some(end) = parts.next() {
+            let (end_book, end_chap, end_verse) = read_osis_id(osis, end, library)?;
+            if end_book != book || end_chap.is_none() {
+                return Err(Diagnostic::MalformedAddress { citation: String::from(osis), element: String::from(end) })
+            }
+            citation.end_chap = end_chap;
+            citation.end_verse = end_verse;
+        }
+        Ok(citation)
     }
 
-    /// Tests for a range or divider element in the citation
-    fn handeling_ranges(&mut self, next_element: Option<&String>, curr_element: &String) -> Address {
-        let curr_citation = &self.curr_citation.clone().map(|x| x).unwrap();
-        let end = String::from("End");
-        let next_element: &String = next_element.unwrap_or(&end);
-        if self.dividers.contains(&next_element.clone()) {
-            self.update_curr_citation(CitationParts::EndChap, curr_element);
-            return Address::Chapter
+    /// Converts the chapters and verses of the citation to another versification scheme
+    /// 
+    /// # Examples
+    /// 
+    /// ```
+    /// use scripcit::citation::address::CitationList;
+    /// use scripcit::citation::book_linking::Library;
+    /// use scripcit::citation::versification::Scheme;
+    /// 
+    /// let library = Library::create().unwrap();
+    /// let mut scriptures = CitationList::new();
+    /// scriptures.scheme = Scheme::Hebrew;
+    /// scriptures.insert("Mal. 3:20", &library);
+    /// let english = scriptures.scrip_vec[0].convert(Scheme::Kjv, &library);
+    /// assert_eq!(english.start_chap, Some(4));
+    /// ```
+    pub fn convert(&self, scheme: Scheme, library: &book_linking::Library) -> ScriptureCitation {
+        let versification = &library.versification;
+        let mut citation = self.clone();
+        if let Some(start_chap) = self.start_chap {
+            let (chapter, verse) = versification.convert(self.book, start_chap, self.start_verse, self.scheme, scheme);
+            citation.start_chap = Some(chapter);
+            citation.start_verse = verse;
         }
-        if curr_citation.start_verse.is_none() {
-            self.update_curr_citation(CitationParts::EndChap, curr_element);
-            return Address::Chapter
+        if let Some(end_chap) = self.end_chap {
+            let (chapter, verse) = versification.convert(self.book, end_chap, self.end_verse, self.scheme, scheme);
+            citation.end_chap = Some(chapter);
+            citation.end_verse = verse;
         }
-        self.update_curr_citation(CitationParts::Verse, curr_element);
-        Address::Verse
+        citation.scheme = scheme;
+        citation
     }
 
-    /// Handles the addition of an element, e.g. 3 and 5. 
-    fn handeling_additions(&mut self, next_element: Option<&String>, curr_element: &String) -> Address {
-        let prev_citation = &self.curr_citation.clone().map(|x| x).unwrap();
-        self.scrip_vec.push(prev_citation.clone());
-        let book = self.book.clone().map(|x| x).unwrap();
-        let mut add_citation = ScriptureCitation::new(&book, None);
-
-        let num = convert_str_to_address_num(curr_element);
-        let end = String::from("End");
+    /// The citation with an open ended `ff.` closed at the last verse of its chapter. 
+    /// Citations in other schemes, or in books without versification data, stay open.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scripcit::{CitationList, Library};
+    ///
+    /// let library = Library::create().unwrap();
+    /// let mut scriptures = CitationList::new();
+    /// scriptures.insert("John 3:16ff.", &library);
+    /// let bounded = scriptures.scrip_vec[0].bounded(&library);
+    /// assert_eq!((bounded.end_chap, bounded.end_verse), (Some(3), Some(36)));
+    /// assert_eq!(bounded.cref(), "John 3:16ff.");
+    /// ```
+    pub fn bounded(&self, library: &book_linking::Library) -> ScriptureCitation {
+        let mut citation = self.clone();
+        if let (Some(Following::Verses), Some(end_chap), None) = (self.following, self.end_chap, self.end_verse) {
+            if self.scheme == Scheme::Kjv {
+                citation.end_verse = library.verse_count(self.book, end_chap);
+            }
+        }
+        citation
+    }
 
-        let next_element: &String = next_element.unwrap_or(&end);
-        if self.dividers.contains(&next_element.clone()) {
-            add_citation.start_chap = num;
-            self.curr_citation = Some(add_citation);
-            return Address::Chapter
+    /// Checks the chapters and verses of the citation against the versification 
+    /// of its book in the library. Books without versification data always pass.
+    /// Citations in other schemes are checked after converting them to the English numbering.
+    pub fn validate(&self, library: &book_linking::Library) -> Result<(), RangeError> {
+        if self.scheme != Scheme::Kjv {
+            return self.convert(Scheme::Kjv, library).validate(library)
+        }
+        let chapters = match library.chapter_count(self.book) {
+            Some(chapters) => chapters,
+            None => return Ok(()),
+        };
+        for (chap, verse) in [(self.start_chap, self.start_verse), (self.end_chap, self.end_verse)].iter() {
+            let chapter = match chap {
+                Some(chapter) => *chapter,
+                None => continue,
+            };
+            if chapter < 1 || chapter > chapters {
+                return Err(RangeError::Chapter { chapter, chapters })
+            }
+            if let Some(verse) = verse {
+                let verses = library.verse_count(self.book, chapter).unwrap_or(0);
+                if *verse < 1 || *verse > verses {
+                    return Err(RangeError::Verse { chapter, verse: *verse, verses })
+                }
+            }
         }
-        add_citation.start_chap = if prev_citation.end_chap.is_none() { prev_citation.start_chap.clone() } else {prev_citation.end_chap.clone()};
-        self.curr_citation = Some(add_citation);
-        self.update_curr_citation(CitationParts::Verse, curr_element);
-        Address::Verse
+        if let (Some(start_chap), Some(end_chap)) = (self.start_chap, self.end_chap) {
+            if end_chap < start_chap {
+                return Err(RangeError::Reversed)
+            }
+            if let (Some(start_verse), Some(end_verse)) = (self.start_verse, self.end_verse) {
+                if end_chap == start_chap && end_verse < start_verse {
+                    return Err(RangeError::Reversed)
+                }
+            }
+        }
+        Ok(())
     }
 
-    /// Create a new CitationList object
-    pub fn new<'b>() -> CitationList  {
-        let ranges: HashSet<String> = [ "-", "–", "–", "—"].iter().map(|x| String::from(*x)).collect();
-        let dividers: HashSet<String> = [":", ".", ","].iter().map(|x| String::from(*x)).collect();
-        // let additions: HashSet<String> = [";", ".", ","].iter().map(|x| String::from(*x)).collect(); 
- 
-        let scrip_vec: Vec<ScriptureCitation> = Vec::new();
+}
 
-        // CitationList {book: None, ranges: ranges, dividers: dividers, additions: additions, curr_citation: None, scrip_vec: scrip_vec}
-        CitationList {book: None, ranges: ranges, dividers: dividers, curr_citation: None, scrip_vec: scrip_vec}
+impl CitationList {
+    /// Create a new CitationList object
+    pub fn new() -> CitationList  {
+        CitationList { scrip_vec: Vec::new(), scheme: Scheme::Kjv }
     }
 
-    /// Takes a raw scripture citation as the scripture_string and a library, and returns a 
-    /// Scripture Citation vector
+    /// Takes a raw scripture citation as the scripture_string and a library, and adds its 
+    /// citations to the Scripture Citation vector. Any problems reading the citation are 
+    /// returned as diagnostics.
     /// 
     /// # Examples
     /// 
     /// ```
+    /// use scripcit::citation::address::{CitationList, ScriptureCitation};
+    /// use scripcit::citation::book_linking::Library;
+    /// use scripcit::citation::versification::Scheme;
+    /// use scripcit::citation::book_id::BookId;
+    /// 
     /// let test = "II Sam. iv. 3-4";
     /// let mut scriptures = CitationList::new();
-    /// let library = book_linking::Library::create().unwrap();
+    /// let library = Library::create().unwrap();
     /// scriptures.insert(test, &library);
-    /// let should_value = ScriptureCitation {book:String::from("2 Samuel"), start_chap:Some(4), start_verse:Some(3), end_chap:Some(4), end_verse:Some(4)};
+    /// let should_value = ScriptureCitation {book:BookId::SecondSamuel, start_chap:Some(4), start_verse:Some(3), start_part: None, end_chap:Some(4), end_verse:Some(4), end_part: None, scheme: Scheme::Kjv, span: None, inferred: false, following: None};
     /// let script = scriptures.scrip_vec[0].clone();
     /// println!("Should: {:?}", should_value);
     /// println!("Script: {:?}", script);
@@ -202,220 +451,60 @@ impl CitationList {
     /// assert_eq!(should_value.end_chap, script.end_chap);
     /// assert_eq!(should_value.end_verse, script.end_verse);
     /// ```
-    pub fn insert(&mut self,  scripture_string: &str, library: &book_linking::Library) {
-        // let citation = ScriptureCitation::new(&script_book.unwrap(), &first_chap.unwrap());
-        let mut prev_element = Address::Book;
-
-        let (book_name, cit_address) = cleaned_book_abbr(scripture_string);
-        let mut scripture_books = library.match_book(&book_name);
-        // let mut script_book = None;
-
-        for (num, book) in scripture_books.drain().enumerate() {
-            if num == 0 {
-                // script_book = Some(book);
-                self.book = Some(book.clone());
-            } else {
-                println!("\x1b[93mDid you mean: {}?\x1b[0m", book)
-            }
-        };
-        if self.book.is_none() {
-            self.scrip_vec = vec![];
-            return 
-        }
-
-        let address_vec: Vec<String> = split_keep(&cit_address);
-        for (num, element) in address_vec.iter().enumerate() {
-            match prev_element {
-                Address::Book => {
-                    self.update_curr_citation(CitationParts::StartChap, element);
-                    prev_element = Address::Chapter;
-                },
-                Address::Chapter => {
-                    prev_element = self.chapter_previous(element);
-                }, 
-                Address::ChapterRange => {
-                    self.update_curr_citation(CitationParts::EndChap, element);
-                    prev_element = Address::Chapter;
-                },
-                Address::Divider => {
-                    self.update_curr_citation(CitationParts::Verse, element);
-                    prev_element = Address::Verse;
-                },
-                Address::Range => {
-                    let next_element = if (num + 1) == address_vec.len() { None } else { Some(&address_vec[num + 1]) };
-                    prev_element = self.handeling_ranges(next_element, element)
-                },
-                Address::Addition => {
-                    let next_element = if (num + 1) == address_vec.len() { None } else { Some(&address_vec[num + 1]) };
-                    prev_element = self.handeling_additions(next_element, element);
-                },
-                Address::Verse => {
-                    prev_element = if self.ranges.contains(element) { Address::Range } else {Address::Addition};
-                },
-
-                _ => {
-                    println!("\x1b[91mError in Formating Citation:\x1b[0m {}", scripture_string);
-                    println!("prev_element {:?}", prev_element);
-                },
-            }
-        }
-        if !self.curr_citation.is_none() {
-            self.scrip_vec.push(self.curr_citation.clone().unwrap());
-        }
+    pub fn insert(&mut self,  scripture_string: &str, library: &book_linking::Library) -> Vec<Diagnostic> {
+        let (mut scriptures, diagnostics) = parser::parse(scripture_string, library, self.scheme);
+        self.scrip_vec.append(&mut scriptures.scrip_vec);
+        diagnostics
     }
-    
-}
-
 
-/// Returns just the book part. 
-/// 
-/// # Examples 
-/// 
-/// ```
-/// let scrip_string = "II Sam. iv. 3, 2";
-/// let mat = grab_book_abbr(scrip_string);
-/// let book = mat.unwrap().as_str();
-/// assert_eq!(book, "ii sam.")
-/// ```
-fn grab_book_abbr(scripture_string: &str) -> Option<regex::Match> {
-    lazy_static! {
-        static ref SCRIPT_ABBREVIATION_REGEX: Regex = Regex::new(r"^(I{1,3}V?|i{1,3}v?|\d{1,3})? ?(\w+).?").unwrap();
+    /// Adds the citations of an address that does not name its book, like `iv. 3-4`, 
+    /// to the given book. Any problems reading the address are returned as diagnostics.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scripcit::{BookId, CitationList, Library};
+    ///
+    /// let library = Library::create().unwrap();
+    /// let mut scriptures = CitationList::new();
+    /// scriptures.insert_address(BookId::Genesis, "2:3", &library);
+    /// assert_eq!(scriptures.scrip_vec[0].cref(), "Genesis 2:3");
+    /// ```
+    pub fn insert_address(&mut self, book: BookId, address: &str, library: &book_linking::Library) -> Vec<Diagnostic> {
+        let (mut scriptures, diagnostics) = parser::parse_address(book, address, library, self.scheme);
+        self.scrip_vec.append(&mut scriptures.scrip_vec);
+        diagnostics
     }
-    SCRIPT_ABBREVIATION_REGEX.find(scripture_string)
-
 }
 
-/// Converts the book number as a string to an i16 number that can be used in 
-/// the scripture citation struct. 
-/// 
-/// # Examples 
-/// 
-/// ```
-/// let num: i16 = 4;
-/// let value = String::from("iv");
-/// let new_value = convert_str_to_address_num(&value);
-/// assert_eq!(num, new_value.unwrap());
-/// ```
-fn convert_str_to_address_num(num: &String) -> Option<i16> {
-    let is_roman = roman_numerals::is_roman_numeral(num);
-    let i16_num = match is_roman {
-        true => Some(roman_numerals::convert_to_numbers(num)),
-        false => num.parse::<i16>().ok(),
-    };
-    i16_num
+impl Default for CitationList {
+    fn default() -> CitationList {
+        CitationList::new()
+    }
 }
 
-/// Return the tuple of the full book name, pluse the book address as a string
-/// 
-/// # Examples 
-/// ```
-/// let scrip_string = "II Sam. iv. 3, 2";
-/// let (book_name, address) = cleaned_book_abbr(scrip_string);
-/// assert_eq!((book_name, address), (String::from("II Sam"), String::from(" iv. 3, 2")));
-/// ```
-fn cleaned_book_abbr(scripture_string: &str) -> (String, String) {
-    let mat = grab_book_abbr(scripture_string).unwrap();
-    let book = mat.as_str().replace(".", "");
-
-    let address = String::from(&scripture_string[mat.end()..]);
-    (book, address)
-}
 
-/// Split the verse and chapter reference into a vector of different elements, 
-/// numbers and dividers, ranges or additions. This makes it easier to iterate over 
-/// the numbers and remember what comes before and after
-///
-/// # Examples 
-/// 
-/// ```
-/// let text = "vi. 1; vii. 3";
-/// let res_vec = split_keep(text);
-/// println!("{:?}", res_vec);
-/// assert_eq!(vec!["vi", ".", "1", ";", "vii", ".", "3"], res_vec);
-/// ```
-fn split_keep(text: &str) -> Vec<String> {
-    lazy_static! {
-        static ref SPLIT_RE: Regex = Regex::new(r"(-|–|–|—|:|\.|,|;|,| ) ?").unwrap();
-    }
-    let mut result: Vec<String> = Vec::new();
-    let mut last = 0;
-    for mat in SPLIT_RE.find_iter(&text) {
-        if mat.start() != last {
-            let input_section: String = String::from(text[last..mat.start()].trim());
-            if input_section != "" {
-                result.push(input_section);
-            }
-        }
-        let matching_str = mat.as_str().trim();
-        if matching_str != "" {
-            result.push(String::from(mat.as_str().trim()));
-        }
-        last = mat.end();
-    }
-    if last < text.len() {
-        result.push(String::from(text[last..].trim()));
-    }
-    result
+/// The verse part as it is written after its verse, or nothing
+fn part(verse_part: Option<VersePart>) -> String {
+    verse_part.map(|verse_part| verse_part.to_string()).unwrap_or_default()
 }
 
-/// The different elements that define an address string.
-#[derive(Debug)]
-enum Address {
-    Book,
-    Chapter,
-    Verse,
-    ChapterRange,
-    Range,
-    Addition,
-    Divider,
-    Error,
+/// The verse part as an OSIS grain, like `!a`, or nothing
+fn grain(verse_part: Option<VersePart>) -> String {
+    verse_part.map(|verse_part| format!("!{}", verse_part)).unwrap_or_default()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    #[test]
-    fn retrive_book_match() {
-        let scrip_string = "II Sam. iv. 3, 2";
-        let mat = grab_book_abbr(scrip_string);
-        let book = mat.unwrap().as_str();
-
-        assert_eq!(book, String::from("II Sam."));
-    }
-
-    #[test]
-    fn cleaning_abbr() {
-        let scrip_string = "II Sam. iv. 3, 2";
-        let (book_name, address) = cleaned_book_abbr(scrip_string);
-
-        assert_eq!((book_name, address), (String::from("II Sam"), String::from(" iv. 3, 2")));
-    }
-
-    #[test]
-    fn test_address_split() {
-        let text = "vi. 1; vii. 3";
-        let res_vec = split_keep(text);
-
-        println!("{:?}", res_vec);
-        assert_eq!(vec!["vi", ".", "1", ";", "vii", ".", "3"], res_vec);
-    }
-
-    #[test]
-    fn string_to_num_test() {
-        let num: i16 = 4;
-        let value = String::from("iv");
-        let new_value = convert_str_to_address_num(&value);
-        assert_eq!(num, new_value.unwrap());
-    }
-
     #[test]
     fn test_verse_range() {
         let test = "II Sam. iv. 3-4";
         let mut scriptures = CitationList::new();
         let library = book_linking::Library::create().unwrap();
         scriptures.insert(test, &library);
-        let should_value = ScriptureCitation {book:String::from("2 Samuel"), start_chap:Some(4), start_verse:Some(3), end_chap:Some(4), end_verse:Some(4)};
+        let should_value = ScriptureCitation {book:BookId::SecondSamuel, start_chap:Some(4), start_verse:Some(3), start_part: None, end_chap:Some(4), end_verse:Some(4), end_part: None, scheme: Scheme::Kjv, span: None, inferred: false, following: None};
         let script = scriptures.scrip_vec[0].clone();
         println!("Should: {:?}", should_value);
         println!("Script: {:?}", script);
@@ -432,21 +521,266 @@ mod tests {
         let mut scriptures = CitationList::new();
         let library = book_linking::Library::create().unwrap();
         scriptures.insert(test, &library);
-        let should_vec = vec![
-            ScriptureCitation {book:String::from("2 Samuel"), start_chap:Some(1), start_verse:Some(3), end_chap:None, end_verse:None}, 
-            ScriptureCitation {book:String::from("2 Samuel"), start_chap:Some(1), start_verse:Some(4), end_chap:None, end_verse:None}, 
+        let should_vec = [
+            ScriptureCitation {book:BookId::SecondSamuel, start_chap:Some(1), start_verse:Some(3), start_part: None, end_chap:None, end_verse:None, end_part: None, scheme: Scheme::Kjv, span: None, inferred: false, following: None}, 
+            ScriptureCitation {book:BookId::SecondSamuel, start_chap:Some(1), start_verse:Some(4), start_part: None, end_chap:None, end_verse:None, end_part: None, scheme: Scheme::Kjv, span: None, inferred: false, following: None}, 
         ];
         assert_eq!(should_vec[0].start_chap, scriptures.scrip_vec[0].start_chap);
         assert_eq!(should_vec[0].start_verse, scriptures.scrip_vec[0].start_verse);
     }
 
+    #[test]
+    fn test_impossible_citation_dropped() {
+        let library = book_linking::Library::create().unwrap();
+        let mut scriptures = CitationList::new();
+        scriptures.insert("Exodus 50:234", &library);
+        assert!(scriptures.scrip_vec.is_empty());
+
+        let mut scriptures = CitationList::new();
+        scriptures.insert("Exod. 40:38; 40:39", &library);
+        assert_eq!(scriptures.scrip_vec.len(), 1);
+        assert_eq!(scriptures.scrip_vec[0].start_verse, Some(38));
+    }
+
+    #[test]
+    fn test_validate_reasons() {
+        let library = book_linking::Library::create().unwrap();
+        let mut citation = ScriptureCitation::new(BookId::Exodus, Some(50));
+        citation.start_verse = Some(1);
+        assert_eq!(citation.validate(&library), Err(RangeError::Chapter { chapter: 50, chapters: 40 }));
+
+        citation.start_chap = Some(40);
+        citation.start_verse = Some(39);
+        assert_eq!(citation.validate(&library), Err(RangeError::Verse { chapter: 40, verse: 39, verses: 38 }));
+
+        citation.start_verse = Some(5);
+        citation.end_chap = Some(40);
+        citation.end_verse = Some(3);
+        assert_eq!(citation.validate(&library), Err(RangeError::Reversed));
+
+        citation.end_verse = Some(38);
+        assert_eq!(citation.validate(&library), Ok(()));
+
+        let apocrypha = ScriptureCitation::new(BookId::Tobit, Some(99));
+        assert_eq!(apocrypha.validate(&library), Ok(()));
+    }
+
+    #[test]
+    fn test_scheme_conversion() {
+        let library = book_linking::Library::create().unwrap();
+        let mut scriptures = CitationList::new();
+        scriptures.scheme = Scheme::Hebrew;
+        scriptures.insert("Mal. 3:19-24", &library);
+        let english = scriptures.scrip_vec[0].convert(Scheme::Kjv, &library);
+        assert_eq!(english.to_string(), "Malachi 4: 1 - 4: 6");
+        assert_eq!(english.convert(Scheme::Hebrew, &library).to_string(), "Malachi 3: 19 - 3: 24");
+    }
+
+    #[test]
+    fn test_scheme_validation() {
+        let library = book_linking::Library::create().unwrap();
+        // Joel has three chapters in English, but four in Hebrew
+        let mut scriptures = CitationList::new();
+        scriptures.scheme = Scheme::Hebrew;
+        scriptures.insert("Joel 4:21", &library);
+        assert_eq!(scriptures.scrip_vec.len(), 1);
+
+        let mut scriptures = CitationList::new();
+        scriptures.insert("Joel 4:21", &library);
+        assert!(scriptures.scrip_vec.is_empty());
+    }
+
+    #[test]
+    fn test_unknown_book() {
+        let library = book_linking::Library::create().unwrap();
+        let mut scriptures = CitationList::new();
+        let diagnostics = scriptures.insert("Xyz 3:4", &library);
+        assert_eq!(diagnostics, vec![Diagnostic::UnknownBook(String::from("Xyz"))]);
+        assert!(scriptures.scrip_vec.is_empty());
+
+        let diagnostics = scriptures.insert("(3:4", &library);
+        assert_eq!(diagnostics, vec![Diagnostic::UnknownBook(String::from("(3:4"))]);
+    }
+
+    #[test]
+    fn test_ambiguous_book() {
+        let library = book_linking::Library::create().unwrap();
+        let mut scriptures = CitationList::new();
+        let diagnostics = scriptures.insert("Ps. 23:1", &library);
+        match &diagnostics[0] {
+            Diagnostic::AmbiguousBook { name, chosen, candidates } => {
+                assert_eq!(name, "Ps");
+                assert!(candidates.contains(&BookId::Psalms));
+                // `Ps` is the OSIS abbreviation of Psalms
+                assert_eq!(*chosen, BookId::Psalms);
+                assert_eq!(&scriptures.scrip_vec[0].book, chosen);
+            },
+            other => panic!("unexpected diagnostic {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_osis() {
+        let library = book_linking::Library::create().unwrap();
+        let mut scriptures = CitationList::new();
+        scriptures.insert("II Sam. iv. 3-4; v. 1", &library);
+        scriptures.insert("Rev. xii. 3", &library);
+        let osis: Vec<String> = scriptures.scrip_vec.iter().map(|citation| citation.to_osis(&library).unwrap()).collect();
+        assert_eq!(osis, vec!["2Sam.4.3-2Sam.4.4", "2Sam.5.1", "Rev.12.3"]);
+        for (citation, osis) in scriptures.scrip_vec.iter().zip(osis.iter()) {
+            assert_eq!(&ScriptureCitation::from_osis(osis, &library).unwrap(), citation);
+        }
+
+        let chapters = ScriptureCitation::from_osis("Ps.23-Ps.24", &library).unwrap();
+        assert_eq!((chapters.book, chapters.start_chap, chapters.end_chap), (BookId::Psalms, Some(23), Some(24)));
+        assert_eq!(chapters.to_osis(&library).unwrap(), "Ps.23-Ps.24");
+        assert_eq!(ScriptureCitation::from_osis("Gen", &library).unwrap().to_osis(&library).unwrap(), "Gen");
+    }
+
+    #[test]
+    fn test_osis_errors() {
+        let library = book_linking::Library::create().unwrap();
+        assert_eq!(ScriptureCitation::from_osis("Foo.1.1", &library), Err(Diagnostic::UnknownBook(String::from("Foo"))));
+        assert!(ScriptureCitation::from_osis("Gen.1.x", &library).is_err());
+        assert!(ScriptureCitation::from_osis("Gen.1.1-Exod.2.1", &library).is_err());
+        assert!(ScriptureCitation::from_osis("Gen.1.1.1", &library).is_err());
+        let citation = ScriptureCitation::new(BookId::Genesis, Some(1));
+        assert_eq!(citation.to_osis(&book_linking::Library::new()), None);
+    }
+
+    #[test]
+    fn test_ranked_book() {
+        let library = book_linking::Library::create().unwrap();
+        let book = |citation: &str| {
+            let mut scriptures = CitationList::new();
+            scriptures.insert(citation, &library);
+            scriptures.scrip_vec[0].book
+        };
+        assert_eq!(book("Jn 3:16"), BookId::John);
+        assert_eq!(book("Lk 2:1"), BookId::Luke);
+        assert_eq!(book("Jas 1:5"), BookId::James);
+        // Joshua, the first book starting with `Jo`, has no 30th chapter
+        assert_eq!(book("Jo 30:1"), BookId::Job);
+    }
+
+    #[test]
+    fn test_malformed_address() {
+        let library = book_linking::Library::create().unwrap();
+        let mut scriptures = CitationList::new();
+        let diagnostics = scriptures.insert("John 3:16g", &library);
+        assert_eq!(diagnostics, vec![Diagnostic::MalformedAddress { citation: String::from("John 3:16g"), element: String::from("16g") }]);
+    }
+
+    #[test]
+    fn test_whole_books_and_chapters() {
+        let library = book_linking::Library::create().unwrap();
+        let shapes = vec![
+            ("Ruth", (None, None, None, None), "Ruth"),
+            ("Isaiah 53", (Some(53), None, None, None), "Isaiah 53"),
+            ("Psalms 120–134", (Some(120), None, Some(134), None), "Psalms 120-134"),
+            ("Rom. iii.-iv.", (Some(3), None, Some(4), None), "Romans 3-4"),
+            ("Gen 1:1–2:3", (Some(1), Some(1), Some(2), Some(3)), "Genesis 1:1-2:3"),
+            ("Rom. 3:27-4:2", (Some(3), Some(27), Some(4), Some(2)), "Romans 3:27-4:2"),
+            ("Rom. 3-4:5", (Some(3), None, Some(4), Some(5)), "Romans 3-4:5"),
+        ];
+        for (text, address, cref) in shapes {
+            let mut scriptures = CitationList::new();
+            let diagnostics = scriptures.insert(text, &library);
+            assert!(diagnostics.iter().all(|diagnostic| matches!(diagnostic, Diagnostic::AmbiguousBook { .. })), "{}", text);
+            let citation = &scriptures.scrip_vec[0];
+            assert_eq!((citation.start_chap, citation.start_verse, citation.end_chap, citation.end_verse), address, "{}", text);
+            assert_eq!(citation.cref(), cref);
+        }
+
+        let mut scriptures = CitationList::new();
+        scriptures.insert("Ps. 120-134; 136", &library);
+        scriptures.insert("Gen. 1:1-2:3; 4:5", &library);
+        let crefs: Vec<String> = scriptures.scrip_vec.iter().map(|citation| citation.cref()).collect();
+        assert_eq!(crefs, vec!["Psalms 120-134", "Psalms 136", "Genesis 1:1-2:3", "Genesis 4:5"]);
+    }
+
+    #[test]
+    fn test_verse_parts() {
+        let library = book_linking::Library::create().unwrap();
+        let mut scriptures = CitationList::new();
+        let diagnostics = scriptures.insert("Rom. 3:21a-26b", &library);
+        assert!(diagnostics.is_empty());
+        let parts = &scriptures.scrip_vec[0];
+        assert_eq!((parts.start_verse, parts.end_verse), (Some(21), Some(26)));
+        assert_eq!(parts.start_part, Some(VersePart { part: 'a', sub_part: None }));
+        assert_eq!(parts.to_string(), "Romans 3: 21a - 3: 26b");
+        assert_eq!(parts.cref(), "Romans 3:21a-26b");
+        assert_eq!(parts.to_usfm(&library).unwrap(), "ROM 3:21a-26b");
+        assert_eq!(parts.to_osis(&library).unwrap(), "Rom.3.21!a-Rom.3.26!b");
+
+        scriptures.insert("Gen. 12:3aα", &library);
+        assert_eq!(scriptures.scrip_vec[1].cref(), "Genesis 12:3aα");
+        let diagnostics = scriptures.insert("Gen. 12:3aαβ", &library);
+        assert!(matches!(&diagnostics[0], Diagnostic::MalformedAddress { element, .. } if element == "3aαβ"));
+    }
+
+    #[test]
+    fn test_following() {
+        let library = book_linking::Library::create().unwrap();
+        let mut scriptures = CitationList::new();
+        let diagnostics = scriptures.insert("John 3:16f.", &library);
+        assert!(diagnostics.is_empty());
+        let following = &scriptures.scrip_vec[0];
+        assert_eq!((following.end_chap, following.end_verse), (Some(3), Some(17)));
+        assert_eq!(following.to_string(), "John 3: 16f.");
+        assert_eq!(following.to_osis(&library).unwrap(), "John.3.16-John.3.17");
+
+        scriptures.insert("John iii. 16ff.", &library);
+        let open = &scriptures.scrip_vec[1];
+        assert_eq!((open.end_chap, open.end_verse, open.following), (Some(3), None, Some(Following::Verses)));
+        assert_eq!(open.cref(), "John 3:16ff.");
+        assert_eq!(open.to_osis(&library).unwrap(), "John.3.16-John.3");
+        assert_eq!(open.bounded(&library).to_osis(&library).unwrap(), "John.3.16-John.3.36");
+    }
+
+    #[test]
+    fn test_impossible_diagnostic() {
+        let library = book_linking::Library::create().unwrap();
+        let mut scriptures = CitationList::new();
+        let diagnostics = scriptures.insert("Exod. 50:234", &library);
+        let mut citation = ScriptureCitation::new(BookId::Exodus, Some(50));
+        citation.start_verse = Some(234);
+        assert_eq!(diagnostics, vec![Diagnostic::ImpossibleRange { citation, reason: RangeError::Chapter { chapter: 50, chapters: 40 } }]);
+    }
+
+    #[test]
+    fn test_single_chapter_books() {
+        let library = book_linking::Library::create().unwrap();
+        assert!(library.single_chapter(BookId::Jude));
+        assert!(!library.single_chapter(BookId::Romans));
+        let mut scriptures = CitationList::new();
+        for text in ["Jude 3", "Jude 1:3", "Philem. 6-8", "Obad. 4, 6", "2 John 12", "3 John i. 4"].iter() {
+            assert!(scriptures.insert(text, &library).is_empty(), "{}", text);
+        }
+        let crefs: Vec<String> = scriptures.scrip_vec.iter().map(|citation| citation.cref()).collect();
+        assert_eq!(crefs, vec!["Jude 1:3", "Jude 1:3", "Philemon 1:6-8", "Obadiah 1:4", "Obadiah 1:6", "2 John 1:12", "3 John 1:4"]);
+
+        let diagnostics = scriptures.insert("Jude 30", &library);
+        assert!(matches!(diagnostics[0], Diagnostic::ImpossibleRange { reason: RangeError::Verse { .. }, .. }));
+    }
+
+    #[test]
+    fn test_list_reuse() {
+        let library = book_linking::Library::create().unwrap();
+        let mut scriptures = CitationList::new();
+        scriptures.insert("Gen. 1:3", &library);
+        scriptures.insert("Isa. 3:1", &library);
+        assert_eq!(scriptures.scrip_vec.len(), 2);
+        assert_eq!(scriptures.scrip_vec[1].to_string(), "Isaiah 3: 1");
+    }
+
     #[test]
     fn test_isa() {
         let test = "Isa. 3:1";
         let mut scriptures = CitationList::new();
         let library = book_linking::Library::create().unwrap();
         scriptures.insert(test, &library);
-        let should = ScriptureCitation {book:String::from("Isaiah"), start_chap:Some(3), start_verse:Some(1), end_chap:None, end_verse:None};
+        let should = ScriptureCitation {book:BookId::Isaiah, start_chap:Some(3), start_verse:Some(1), start_part: None, end_chap:None, end_verse:None, end_part: None, scheme: Scheme::Kjv, span: None, inferred: false, following: None};
         println!("{:?}", scriptures.scrip_vec);
         assert_eq!(should.book, scriptures.scrip_vec[0].book);
     }
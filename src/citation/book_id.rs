@@ -0,0 +1,365 @@
+//! The canonical books that citations can point to.
+//!
+//! Every citation stores its book as a `BookId`, so that comparing, sorting and hashing
+//! citations never depends on how the book is spelled. The books are declared in the
+//! order of `data/books.csv`: the Old Testament, the Apocrypha and Pseudepigrapha, and
+//! the New Testament. That order is the canonical order used for sorting. Books added by
+//! a layered book file come after them, in the order they were added.
+use std::fmt;
+use std::str::FromStr;
+use std::sync::RwLock;
+use serde::{Serialize, Serializer};
+
+lazy_static! {
+    /// The names of the books added by layered book files, in the order they were added
+    static ref OTHER_BOOKS: RwLock<Vec<&'static str>> = RwLock::new(Vec::new());
+}
+
+/// A canonical book. The English name is only produced when the book is displayed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum BookId {
+    Genesis,
+    Exodus,
+    Leviticus,
+    Numbers,
+    Deuteronomy,
+    Joshua,
+    Judges,
+    Ruth,
+    FirstSamuel,
+    SecondSamuel,
+    FirstKings,
+    SecondKings,
+    FirstChronicles,
+    SecondChronicles,
+    Ezra,
+    Nehemiah,
+    Esther,
+    Job,
+    Psalms,
+    Proverbs,
+    Ecclesiastes,
+    SongOfSongs,
+    Isaiah,
+    Jeremiah,
+    Lamentations,
+    Ezekiel,
+    Daniel,
+    Hosea,
+    Joel,
+    Amos,
+    Obadiah,
+    Jonah,
+    Micah,
+    Nahum,
+    Habakkuk,
+    Zephaniah,
+    Haggai,
+    Zechariah,
+    Malachi,
+    FirstEsdras,
+    SecondEsdras,
+    Tobit,
+    Judith,
+    WisdomOfSolomon,
+    Ecclesiasticus,
+    Baruch,
+    LetterOfJeremiah,
+    FirstMaccabees,
+    SecondMaccabees,
+    ThirdMaccabees,
+    FourthMaccabees,
+    Jubilees,
+    PsalmsOfSolomon,
+    Enoch,
+    Matthew,
+    Mark,
+    Luke,
+    John,
+    Acts,
+    Romans,
+    FirstCorinthians,
+    SecondCorinthians,
+    Galatians,
+    Ephesians,
+    Philippians,
+    Colossians,
+    FirstThessalonians,
+    SecondThessalonians,
+    FirstTimothy,
+    SecondTimothy,
+    Titus,
+    Philemon,
+    Hebrews,
+    James,
+    FirstPeter,
+    SecondPeter,
+    FirstJohn,
+    SecondJohn,
+    ThirdJohn,
+    Jude,
+    Revelation,
+    /// A book that is not in `data/books.csv`, like the Gospel of Thomas, added by a
+    /// layered book file
+    Other(u16),
+}
+
+impl BookId {
+    /// Every canonical book, in canonical order
+    pub const ALL: [BookId; 81] = [
+        BookId::Genesis,
+        BookId::Exodus,
+        BookId::Leviticus,
+        BookId::Numbers,
+        BookId::Deuteronomy,
+        BookId::Joshua,
+        BookId::Judges,
+        BookId::Ruth,
+        BookId::FirstSamuel,
+        BookId::SecondSamuel,
+        BookId::FirstKings,
+        BookId::SecondKings,
+        BookId::FirstChronicles,
+        BookId::SecondChronicles,
+        BookId::Ezra,
+        BookId::Nehemiah,
+        BookId::Esther,
+        BookId::Job,
+        BookId::Psalms,
+        BookId::Proverbs,
+        BookId::Ecclesiastes,
+        BookId::SongOfSongs,
+        BookId::Isaiah,
+        BookId::Jeremiah,
+        BookId::Lamentations,
+        BookId::Ezekiel,
+        BookId::Daniel,
+        BookId::Hosea,
+        BookId::Joel,
+        BookId::Amos,
+        BookId::Obadiah,
+        BookId::Jonah,
+        BookId::Micah,
+        BookId::Nahum,
+        BookId::Habakkuk,
+        BookId::Zephaniah,
+        BookId::Haggai,
+        BookId::Zechariah,
+        BookId::Malachi,
+        BookId::FirstEsdras,
+        BookId::SecondEsdras,
+        BookId::Tobit,
+        BookId::Judith,
+        BookId::WisdomOfSolomon,
+        BookId::Ecclesiasticus,
+        BookId::Baruch,
+        BookId::LetterOfJeremiah,
+        BookId::FirstMaccabees,
+        BookId::SecondMaccabees,
+        BookId::ThirdMaccabees,
+        BookId::FourthMaccabees,
+        BookId::Jubilees,
+        BookId::PsalmsOfSolomon,
+        BookId::Enoch,
+        BookId::Matthew,
+        BookId::Mark,
+        BookId::Luke,
+        BookId::John,
+        BookId::Acts,
+        BookId::Romans,
+        BookId::FirstCorinthians,
+        BookId::SecondCorinthians,
+        BookId::Galatians,
+        BookId::Ephesians,
+        BookId::Philippians,
+        BookId::Colossians,
+        BookId::FirstThessalonians,
+        BookId::SecondThessalonians,
+        BookId::FirstTimothy,
+        BookId::SecondTimothy,
+        BookId::Titus,
+        BookId::Philemon,
+        BookId::Hebrews,
+        BookId::James,
+        BookId::FirstPeter,
+        BookId::SecondPeter,
+        BookId::FirstJohn,
+        BookId::SecondJohn,
+        BookId::ThirdJohn,
+        BookId::Jude,
+        BookId::Revelation,
+    ];
+
+    /// The position of the book in the canonical order, starting from 0
+    pub fn index(self) -> usize {
+        match self {
+            BookId::Other(other) => BookId::ALL.len() + other as usize,
+            book => BookId::ALL.iter().position(|canonical| *canonical == book).unwrap_or(0),
+        }
+    }
+
+    /// The book with the given canonical name, adding it after the other books if it is
+    /// not one of them yet
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scripcit::BookId;
+    ///
+    /// let thomas = BookId::register("Gospel of Thomas");
+    /// assert_eq!(thomas.name(), "Gospel of Thomas");
+    /// assert_eq!(BookId::register("gospel of thomas"), thomas);
+    /// assert_eq!(BookId::register("Ruth"), BookId::Ruth);
+    /// ```
+    pub fn register(name: &str) -> BookId {
+        if let Ok(book) = name.parse::<BookId>() {
+            return book
+        }
+        let mut others = OTHER_BOOKS.write().unwrap();
+        if let Some(other) = others.iter().position(|other| other.eq_ignore_ascii_case(name.trim())) {
+            return BookId::Other(other as u16)
+        }
+        others.push(Box::leak(String::from(name.trim()).into_boxed_str()));
+        BookId::Other(others.len() as u16 - 1)
+    }
+
+    /// The English canonical name of the book, as in the first column of `data/books.csv`
+    pub fn name(self) -> &'static str {
+        match self {
+            BookId::Genesis => "Genesis",
+            BookId::Exodus => "Exodus",
+            BookId::Leviticus => "Leviticus",
+            BookId::Numbers => "Numbers",
+            BookId::Deuteronomy => "Deuteronomy",
+            BookId::Joshua => "Joshua",
+            BookId::Judges => "Judges",
+            BookId::Ruth => "Ruth",
+            BookId::FirstSamuel => "1 Samuel",
+            BookId::SecondSamuel => "2 Samuel",
+            BookId::FirstKings => "1 Kings",
+            BookId::SecondKings => "2 Kings",
+            BookId::FirstChronicles => "1 Chronicles",
+            BookId::SecondChronicles => "2 Chronicles",
+            BookId::Ezra => "Ezra",
+            BookId::Nehemiah => "Nehemiah",
+            BookId::Esther => "Esther",
+            BookId::Job => "Job",
+            BookId::Psalms => "Psalms",
+            BookId::Proverbs => "Proverbs",
+            BookId::Ecclesiastes => "Ecclesiastes",
+            BookId::SongOfSongs => "Song of Songs",
+            BookId::Isaiah => "Isaiah",
+            BookId::Jeremiah => "Jeremiah",
+            BookId::Lamentations => "Lamentations",
+            BookId::Ezekiel => "Ezekiel",
+            BookId::Daniel => "Daniel",
+            BookId::Hosea => "Hosea",
+            BookId::Joel => "Joel",
+            BookId::Amos => "Amos",
+            BookId::Obadiah => "Obadiah",
+            BookId::Jonah => "Jonah",
+            BookId::Micah => "Micah",
+            BookId::Nahum => "Nahum",
+            BookId::Habakkuk => "Habakkuk",
+            BookId::Zephaniah => "Zephaniah",
+            BookId::Haggai => "Haggai",
+            BookId::Zechariah => "Zechariah",
+            BookId::Malachi => "Malachi",
+            BookId::FirstEsdras => "1 Esdras",
+            BookId::SecondEsdras => "2 Esdras",
+            BookId::Tobit => "Tobit",
+            BookId::Judith => "Judith",
+            BookId::WisdomOfSolomon => "Wisdom of Solomon",
+            BookId::Ecclesiasticus => "Ecclesiasticus",
+            BookId::Baruch => "Baruch",
+            BookId::LetterOfJeremiah => "Letter of Jeremiah",
+            BookId::FirstMaccabees => "1 Maccabees",
+            BookId::SecondMaccabees => "2 Maccabees",
+            BookId::ThirdMaccabees => "3 Maccabees",
+            BookId::FourthMaccabees => "4 Maccabees",
+            BookId::Jubilees => "Jubilees",
+            BookId::PsalmsOfSolomon => "Psalms of Solomon",
+            BookId::Enoch => "Enoch",
+            BookId::Matthew => "Matthew",
+            BookId::Mark => "Mark",
+            BookId::Luke => "Luke",
+            BookId::John => "John",
+            BookId::Acts => "Acts of the Apostles",
+            BookId::Romans => "Romans",
+            BookId::FirstCorinthians => "1 Corinthians",
+            BookId::SecondCorinthians => "2 Corinthians",
+            BookId::Galatians => "Galatians",
+            BookId::Ephesians => "Ephesians",
+            BookId::Philippians => "Philippians",
+            BookId::Colossians => "Colossians",
+            BookId::FirstThessalonians => "1 Thessalonians",
+            BookId::SecondThessalonians => "2 Thessalonians",
+            BookId::FirstTimothy => "1 Timothy",
+            BookId::SecondTimothy => "2 Timothy",
+            BookId::Titus => "Titus",
+            BookId::Philemon => "Philemon",
+            BookId::Hebrews => "Hebrews",
+            BookId::James => "James",
+            BookId::FirstPeter => "1 Peter",
+            BookId::SecondPeter => "2 Peter",
+            BookId::FirstJohn => "1 John",
+            BookId::SecondJohn => "2 John",
+            BookId::ThirdJohn => "3 John",
+            BookId::Jude => "Jude",
+            BookId::Revelation => "Revelation",
+            BookId::Other(other) => OTHER_BOOKS.read().unwrap().get(other as usize).copied().unwrap_or(""),
+        }
+    }
+}
+
+impl fmt::Display for BookId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for BookId {
+    type Err = String;
+
+    /// Reads the exact canonical name of a book, ignoring case
+    fn from_str(name: &str) -> Result<BookId, String> {
+        let name = name.trim();
+        let others = OTHER_BOOKS.read().unwrap();
+        BookId::ALL.iter()
+            .find(|book| book.name().eq_ignore_ascii_case(name))
+            .copied()
+            .or_else(|| others.iter().position(|other| other.eq_ignore_ascii_case(name)).map(|other| BookId::Other(other as u16)))
+            .ok_or_else(|| format!("unknown canonical book: {}", name))
+    }
+}
+
+impl Serialize for BookId {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.name())
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn canonical_order_test() {
+        assert_eq!(BookId::Genesis.index(), 0);
+        assert_eq!(BookId::Revelation.index(), 80);
+        assert!(BookId::Malachi < BookId::Tobit);
+        assert!(BookId::Enoch < BookId::Matthew);
+        for (i, book) in BookId::ALL.iter().enumerate() {
+            assert_eq!(book.index(), i);
+        }
+    }
+
+    #[test]
+    fn book_from_str_test() {
+        assert_eq!("2 Samuel".parse::<BookId>(), Ok(BookId::SecondSamuel));
+        assert_eq!("acts of the apostles".parse::<BookId>(), Ok(BookId::Acts));
+        assert_eq!(BookId::SongOfSongs.to_string(), "Song of Songs");
+        assert!("2 Sam".parse::<BookId>().is_err());
+    }
+}
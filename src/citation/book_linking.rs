@@ -1,27 +1,49 @@
 //! This creates a data structure from the data/books.csv 
 //! which is then checked against which ever book is found 
 //! in the regex. 
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeSet, HashMap};
 use std::cmp::Ordering;
 use regex::Regex;
-use std::io::{BufReader, BufRead};
 use std::error::Error;
-use std::fs::File;
+use std::fs;
+use std::path::Path;
+
+use super::book_id::BookId;
+use super::roman_numerals;
+use super::versification::Versification;
+
+/// The canonical book table. It is compiled into the binary so that the library 
+/// can be created no matter which directory the tool is run from. The header names 
+/// the columns, the `osis` column is the OSIS abbreviation of the book, the `usfm` 
+/// column is its USFM book code, and every other column is a name that can be matched.
+const BOOKS_CSV: &str = include_str!("../../data/books.csv");
+
+/// The number of verses in every chapter of the canonical books, following the 
+/// common English (KJV) numbering. Each line is the canonical name followed by the 
+/// verse count of each chapter in order.
+const VERSES_CSV: &str = include_str!("../../data/verses.csv");
 
-#[path = "./roman_numerals/lib.rs"] mod roman_numerals; 
 /// The basic structure of the book, if it has a number, the sorting character
-/// The canonical name, and its id. 
+/// The canonical book, and its id. 
 pub struct Book {
     pub num: Option<i16>,
     pub sort_on: char,
     pub name: String,
-    pub canonical_name: String,
+    pub canonical: BookId,
     pub idx: HashMap<char, Vec<usize>>,
 }
 
-/// All of the structs of the books are added to the Library.
+/// All of the structs of the books are added to the Library, along with the 
+/// number of verses in each chapter of the canonical books, and the mappings 
+/// between versification schemes.
 pub struct Library {
     pub items: HashMap<char, Vec<Book>>,
+    pub chapters: HashMap<BookId, Vec<i16>>,
+    /// The OSIS abbreviation of each canonical book
+    pub osis: HashMap<BookId, String>,
+    /// The three letter USFM code of each canonical book
+    pub usfm: HashMap<BookId, String>,
+    pub versification: Versification,
 }
 
 
@@ -30,68 +52,232 @@ impl Library {
     /// This function creates the library from the data set 
     pub fn create() -> Result<Library, Box<dyn Error>> {
         let mut library_collection = Library::new();
-        let file= File::open("data/books.csv")?;
-        let reader = BufReader::new(file);
-        for line in reader.lines() {
-            let books = line.unwrap();
-            let mut book_iter = books.split(",");
-            let canonical_name = book_iter.next().unwrap();
-            let first_book = Book::new(canonical_name, canonical_name);
-            library_collection.add(first_book);
-            for book in book_iter {
-                if book != "" {
-                    let next_book = Book::new(book, canonical_name);
-                    library_collection.add(next_book);
+        library_collection.add_csv(BOOKS_CSV)?;
+        library_collection.add_verses(VERSES_CSV)?;
+        library_collection.versification = Versification::create()?;
+        Ok(library_collection)
+    }
+
+    /// Layers a user supplied book file on top of the library. The file has the same 
+    /// layout as `data/books.csv`: the canonical name first, followed by any other 
+    /// names or abbreviations that should match it. The header line is optional, without 
+    /// it every column after the first is read as a name. A canonical name that is not one 
+    /// of the canonical books adds a new book after them, like `BookId::register`.
+    pub fn load(&mut self, path: &Path) -> Result<(), Box<dyn Error>> {
+        let contents = fs::read_to_string(path)?;
+        self.add_csv(&contents)
+    }
+
+    /// Adds every line of a book csv to the library
+    fn add_csv(&mut self, contents: &str) -> Result<(), Box<dyn Error>> {
+        let mut lines = contents.lines().peekable();
+        let mut columns: Vec<String> = Vec::new();
+        if let Some(header) = lines.peek() {
+            if header.split(',').next().map(|field| field.trim()) == Some("canonical") {
+                columns = header.split(',').map(|field| field.trim().to_lowercase()).collect();
+                lines.next();
+            }
+        }
+        for line in lines {
+            let mut book_iter = line.split(',');
+            let canonical_name = book_iter.next().unwrap_or("").trim();
+            if book_split(canonical_name).1.is_empty() {
+                continue
+            }
+            let canonical = BookId::register(canonical_name);
+            let first_book = Book::new(canonical_name, canonical);
+            self.add(first_book);
+            for (i, book) in book_iter.enumerate() {
+                if book.trim().is_empty() {
+                    continue
+                }
+                match columns.get(i + 1).map(|column| column.as_str()) {
+                    Some("osis") => {
+                        self.osis.insert(canonical, String::from(book.trim()));
+                    },
+                    Some("usfm") => {
+                        self.usfm.insert(canonical, book.trim().to_uppercase());
+                    },
+                    _ if !book_split(book).1.is_empty() => {
+                        let next_book = Book::new(book, canonical);
+                        self.add(next_book);
+                    },
+                    _ => (),
                 }
             }
         }
-        Ok(library_collection)
+        Ok(())
+    }
+
+    /// Adds the verses per chapter of each canonical book to the library
+    fn add_verses(&mut self, contents: &str) -> Result<(), Box<dyn Error>> {
+        for line in contents.lines() {
+            let mut count_iter = line.split(',');
+            let canonical_name = count_iter.next().unwrap_or("").trim();
+            if canonical_name.is_empty() {
+                continue
+            }
+            let mut verses = Vec::new();
+            for count in count_iter {
+                verses.push(count.trim().parse::<i16>()?);
+            }
+            self.chapters.insert(canonical_name.parse()?, verses);
+        }
+        Ok(())
     }
 
     /// a new library is a new initialization of the data set.
     pub fn new() -> Library {
-        Library { items: HashMap::new() }
+        Library { items: HashMap::new(), chapters: HashMap::new(), osis: HashMap::new(), usfm: HashMap::new(), versification: Versification::new() }
+    }
+
+    /// Returns the OSIS abbreviation of a canonical book
+    pub fn osis_id(&self, book: BookId) -> Option<&str> {
+        self.osis.get(&book).map(|osis| osis.as_str())
+    }
+
+    /// Returns the canonical book of an OSIS abbreviation
+    pub fn osis_book(&self, osis: &str) -> Option<BookId> {
+        self.osis.iter()
+            .find(|(_, id)| id.as_str() == osis)
+            .map(|(book, _)| *book)
+    }
+
+    /// Returns the USFM code of a canonical book
+    pub fn usfm_id(&self, book: BookId) -> Option<&str> {
+        self.usfm.get(&book).map(|usfm| usfm.as_str())
+    }
+
+    /// Returns the canonical book of a USFM code
+    pub fn usfm_book(&self, usfm: &str) -> Option<BookId> {
+        let usfm = usfm.to_uppercase();
+        self.usfm.iter()
+            .find(|(_, id)| **id == usfm)
+            .map(|(book, _)| *book)
+    }
+
+    /// Returns the number of chapters in a canonical book, if its versification is known.
+    pub fn chapter_count(&self, book: BookId) -> Option<i16> {
+        self.chapters.get(&book).map(|verses| verses.len() as i16)
+    }
+
+    /// Whether the book has only one chapter, like Jude or Philemon, so that a lone number 
+    /// after it is a verse.
+    pub fn single_chapter(&self, book: BookId) -> bool {
+        self.chapter_count(book) == Some(1)
+    }
+
+    /// Returns the number of verses in a chapter of a canonical book, if the 
+    /// versification is known and the chapter exists.
+    pub fn verse_count(&self, book: BookId, chapter: i16) -> Option<i16> {
+        if chapter < 1 {
+            return None
+        }
+        let verses = self.chapters.get(&book)?;
+        verses.get(chapter as usize - 1).copied()
     }
 
     /// Add, can add a book to the library struct
     pub fn add(&mut self, book: Book) {
-        if !&self.items.contains_key(&book.sort_on) {
-            self.items.insert(book.sort_on, Vec::new());
-        }
-        if let Some(book_vec) = self.items.get_mut(&book.sort_on) {
-            book_vec.push(book);
-        }
+        self.items.entry(book.sort_on).or_default().push(book);
     }
 
     /// The primary function of the Library is to match books against it. 
-    /// This function matches a given string against the Hashmap of the library.
+    /// This function matches a given string against the Hashmap of the library, 
+    /// and returns the possible canonical books in canonical order. A name written out in 
+    /// full only matches its own book.
     /// 
     /// # Examples
     /// ``` 
+    /// use scripcit::citation::book_id::BookId;
+    /// use scripcit::citation::book_linking::Library;
+    /// 
     /// // create the library
     /// let library = Library::create().unwrap();
     /// // abbreviation to test
     /// let abbrev = "ii Sam";
-    /// let mut expected_return: std::collections::HashSet<String> = std::collections::HashSet::new();
-    /// // the match returns an array, insert the expected match
-    /// expected_return.insert(String::from("2 Samuel"));
+    /// let mut expected_return = std::collections::BTreeSet::new();
+    /// // the match returns a set, insert the expected match
+    /// expected_return.insert(BookId::SecondSamuel);
     /// let actual_return = library.match_book(abbrev);
     /// assert_eq!(actual_return, expected_return);
     /// ```
-    pub fn match_book(&self, book_to_match: &str) -> HashSet<String> {
+    pub fn match_book(&self, book_to_match: &str) -> BTreeSet<BookId> {
+        self.rank_books(book_to_match).into_iter().collect()
+    }
+
+    /// Matches a name like `match_book`, with the best matches first. The OSIS abbreviation 
+    /// of a book, like `Jas` for `James`, comes first, then a name that starts a book, like 
+    /// `Lu` for `Luke`, and then one that only has its letters in order, where the one that 
+    /// leaves out the fewest letters comes first. An English name comes before the same 
+    /// match in another language, so `Jn` is John rather than Jona. Matches that are as good 
+    /// are in canonical order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scripcit::{BookId, Library};
+    ///
+    /// let library = Library::create().unwrap();
+    /// assert_eq!(library.rank_books("Jn")[0], BookId::John);
+    /// assert_eq!(library.rank_books("Lk")[0], BookId::Luke);
+    /// ```
+    pub fn rank_books(&self, book_to_match: &str) -> Vec<BookId> {
         let (num, other_book) = book_split(book_to_match);
-        let mut possible_matches = HashSet::new();
-        let first_char = other_book.chars().next().unwrap();
-        if !&self.items.contains_key(&first_char) {
-            return possible_matches
+        let books = match other_book.chars().next().and_then(|first_char| self.items.get(&first_char)) {
+            Some(books) => books,
+            None => return Vec::new(),
+        };
+        let mut best: HashMap<BookId, (u8, usize, bool)> = HashMap::new();
+        let osis = self.osis.iter()
+            .filter(|(_, osis)| book_split(osis) == (num, other_book.clone()))
+            .map(|(book, _)| (*book, (1, 0, false)));
+        let names = books.iter()
+            .filter_map(|book| book.match_quality(&other_book, &num).map(|quality| (book.canonical, quality)));
+        for (book, quality) in osis.chain(names) {
+            let entry = best.entry(book).or_insert(quality);
+            *entry = (*entry).min(quality);
         }
-
-        for book in self.items.get(&first_char).unwrap() {
-            if book.name_match(&other_book, &num) {
-                possible_matches.insert(book.canonical_name.clone());
-            }
+        // a book written out in full, like `Jude`, is not also the start of another, like `Judges`
+        if best.values().any(|quality| quality.0 == 0) {
+            best.retain(|_, quality| quality.0 == 0);
         }
-        possible_matches
+        let mut ranked: Vec<(BookId, (u8, usize, bool))> = best.into_iter().collect();
+        ranked.sort_by_key(|(book, quality)| (*quality, *book));
+        ranked.into_iter().map(|(book, _)| book).collect()
+    }
+
+    /// Finds the books with exactly the given name or OSIS abbreviation, like `Isaiah` or 
+    /// `Isa`, without the partial matches of `match_book`. This is for words that may not 
+    /// be books at all, so `Is` and `Number` are not books.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scripcit::{BookId, Library};
+    ///
+    /// let library = Library::create().unwrap();
+    /// assert!(library.match_book_name("1 Cor.").contains(&BookId::FirstCorinthians));
+    /// assert!(library.match_book_name("Is").is_empty());
+    /// ```
+    pub fn match_book_name(&self, book_to_match: &str) -> BTreeSet<BookId> {
+        let (num, other_book) = book_split(book_to_match.trim_end_matches('.'));
+        let names = other_book.chars().next()
+            .and_then(|first_char| self.items.get(&first_char))
+            .into_iter()
+            .flatten()
+            .filter(|book| book.num == num && book.name == other_book)
+            .map(|book| book.canonical);
+        let osis = self.osis.iter()
+            .filter(|(_, osis)| book_split(osis) == (num, other_book.clone()))
+            .map(|(book, _)| *book);
+        names.chain(osis).collect()
+    }
+}
+
+impl Default for Library {
+    fn default() -> Library {
+        Library::new()
     }
 }
 
@@ -99,7 +285,7 @@ impl Book {
     /// Initiate a new Book struct. The name is any form of the name that can be seen 
     /// in the texts. In our data set this includes names in German, French, Spanish, and English, 
     /// as well as common abbreviations.
-    pub fn new(name_str: &str, canonical_name: &str) -> Book {
+    pub fn new(name_str: &str, canonical: BookId) -> Book {
         // let mut idx: HashMap<char, Vec<usize>> = HashMap::new();
         let (num, name) = book_split(name_str);
         let mut idx = HashMap::new();
@@ -114,11 +300,11 @@ impl Book {
         }
             
         Book {
-            num: num, 
-            sort_on: sort_on,
-            name: String::from(name), 
-            canonical_name: String::from(canonical_name),
-            idx: idx
+            num, 
+            sort_on,
+            name, 
+            canonical,
+            idx
         }
     }
 
@@ -132,13 +318,31 @@ impl Book {
             if value.is_none() {
                 return false
             }
-            if value.unwrap() == false {
+            if !value.unwrap() {
                 return false
             }
         }
         true
     }
 
+    /// How well the name matches the book, lower being better: 0 for the whole name, 2 
+    /// for the start of it, and 3 for its letters in order, then the number of letters of 
+    /// the book that were left out, and last whether the book is not written with its English 
+    /// canonical name. None if the name does not match.
+    pub fn match_quality(&self, other_name: &str, other_num: &Option<i16>) -> Option<(u8, usize, bool)> {
+        if !self.name_match(other_name, other_num) {
+            return None
+        }
+        let other_form = book_split(self.canonical.name()).1 != self.name;
+        if self.name == other_name {
+            Some((0, 0, other_form))
+        } else if self.ordered_name_match(other_name) {
+            Some((2, 0, other_form))
+        } else {
+            Some((3, self.name.chars().count().saturating_sub(other_name.chars().count()), other_form))
+        }
+    }
+
     /// name match will first look for an ordered match, and then look for a match of all 
     /// subsequent matches. So `Mrk` will match with `Mark` even though one of the letters 
     /// is missing
@@ -151,7 +355,7 @@ impl Book {
         }
         let mut last = 0;
         for c in other_name.chars() {
-            if !&self.idx.contains_key(&c) {
+            if !self.idx.contains_key(&c) {
                 return false
             }
             let char_vec = &self.idx.get(&c);
@@ -174,6 +378,8 @@ impl Book {
 /// # Examples 
 /// 
 /// ```
+/// use scripcit::citation::book_linking::book_split;
+/// 
 /// let book = "ii Samuel";
 /// let (num, name) = book_split(book);
 /// assert_eq!(num, Some(2));
@@ -187,7 +393,7 @@ pub fn book_split(book_name: &str) -> (Option<i16>, String) {
     let mat_opt = BOOK_RE.find(book_name);
     if mat_opt.is_none() {
         let name = book_name.trim().to_lowercase();
-        return (None, String::from(name))
+        return (None, name)
     }
 
     let mat = mat_opt.unwrap();
@@ -195,7 +401,8 @@ pub fn book_split(book_name: &str) -> (Option<i16>, String) {
     let num = value.parse::<i16>();
     let inum: Option<i16>  = match num.is_err() {
         false => Some(num.unwrap()),
-        true => Some(roman_numerals::convert_to_numbers(value)),
+        true if roman_numerals::is_roman_numeral(value) => Some(roman_numerals::convert_to_numbers(value)),
+        true => return (None, book_name.trim().to_lowercase()),
     };
     for (i, chr) in book_name.chars().enumerate() {
         if i >= mat.end() {
@@ -208,20 +415,21 @@ pub fn book_split(book_name: &str) -> (Option<i16>, String) {
 
 
 #[cfg(test)]
+#[allow(clippy::bool_assert_comparison)]
 mod tests {
     use super::*;
     // use crate::citation::roman_numerals::*;
 
     #[test]
     fn consec_abbrev_match_test() {
-        let nbook = Book::new("Genesis", "Genesis"); // {num: None, name: String::from("Genesis"), idx: None};
+        let nbook = Book::new("Genesis", BookId::Genesis); // {num: None, name: String::from("Genesis"), idx: None};
         let (num, abbrev) = book_split("Gen");
         assert_eq!(nbook.name_match(&abbrev, &num), true);
     }
 
     #[test]
     fn irregular_abbrev_match_test() {
-        let nbook = Book::new("John", "John");
+        let nbook = Book::new("John", BookId::John);
         let (num, abbrev) = book_split("Jn");
         assert!(nbook.name_match(&abbrev, &num));
         let (nnum, nabbrev) = book_split("Joh");
@@ -233,7 +441,7 @@ mod tests {
     #[test]
     fn match_book_and_title() {
         let (num, abbrev) = book_split("2 Kgs");
-        let book = Book::new("2 Kings", "2 Kings");
+        let book = Book::new("2 Kings", BookId::SecondKings);
         assert!(book.name_match(&abbrev, &num));
 
     }
@@ -250,8 +458,8 @@ mod tests {
     fn archive_search_test() {
         let library = Library::create().unwrap();
         let abbrev = "ii Sam";
-        let mut expected_return: std::collections::HashSet<String> = std::collections::HashSet::new();
-        expected_return.insert(String::from("2 Samuel"));
+        let mut expected_return = BTreeSet::new();
+        expected_return.insert(BookId::SecondSamuel);
         let actual_return = library.match_book(abbrev);
         let (num, nab) = book_split(abbrev);
         println!("{}, {}", num.unwrap(), nab);
@@ -263,8 +471,8 @@ mod tests {
     fn kings_archive_search_test() {
        let library = Library::create().unwrap();
         let abbrev = "2Kng";
-        let mut expected_return: std::collections::HashSet<String> = std::collections::HashSet::new();
-        expected_return.insert(String::from("2 Kings"));
+        let mut expected_return = BTreeSet::new();
+        expected_return.insert(BookId::SecondKings);
         let actual_return = library.match_book(abbrev);
         let (num, nab) = book_split(abbrev);
         println!("{}, {}", num.unwrap(), nab);
@@ -276,11 +484,83 @@ mod tests {
     fn isa_search() {
         let library = Library::create().unwrap();
         let abbrev = "Isa";
-        let mut expected_return: std::collections::HashSet<String> = std::collections::HashSet::new();
-        expected_return.insert(String::from("Isaiah"));
+        let mut expected_return = BTreeSet::new();
+        expected_return.insert(BookId::Isaiah);
 
         let actual_return = library.match_book(abbrev);
         assert_eq!(actual_return, expected_return);
     }
 
+    #[test]
+    fn chronicles_search() {
+        let library = Library::create().unwrap();
+        let mut expected_return = BTreeSet::new();
+        expected_return.insert(BookId::SecondChronicles);
+        assert_eq!(library.match_book("2 Chron"), expected_return);
+    }
+
+    #[test]
+    fn verse_count_test() {
+        let library = Library::create().unwrap();
+        assert_eq!(library.chapter_count(BookId::Exodus), Some(40));
+        assert_eq!(library.verse_count(BookId::Exodus, 40), Some(38));
+        assert_eq!(library.verse_count(BookId::Psalms, 119), Some(176));
+        assert_eq!(library.verse_count(BookId::Exodus, 41), None);
+        assert_eq!(library.verse_count(BookId::Exodus, 0), None);
+        assert_eq!(library.chapter_count(BookId::Tobit), None);
+
+        let total: i32 = library.chapters.values().flatten().map(|v| *v as i32).sum();
+        assert_eq!(total, 31102);
+    }
+
+    #[test]
+    fn osis_test() {
+        let library = Library::create().unwrap();
+        assert_eq!(library.osis_id(BookId::FirstCorinthians), Some("1Cor"));
+        assert_eq!(library.osis_book("Phlm"), Some(BookId::Philemon));
+        assert_eq!(library.osis.len(), 81);
+        assert_eq!(library.usfm_id(BookId::FirstCorinthians), Some("1CO"));
+        assert_eq!(library.usfm_book("jhn"), Some(BookId::John));
+        assert_eq!(library.usfm.len(), 81);
+        // the header is not read as a book
+        assert!(library.match_book("canonical").is_empty());
+    }
+
+    #[test]
+    fn unmatched_search_test() {
+        let library = Library::create().unwrap();
+        assert!(library.match_book("").is_empty());
+        assert!(library.match_book("12").is_empty());
+        assert!(library.match_book("Xyz").is_empty());
+    }
+
+    #[test]
+    fn layered_library_test() {
+        let path = std::env::temp_dir().join(format!("scripcit_layered_books_{}.csv", std::process::id()));
+        fs::write(&path, "Genesis,Bereshit,\n\nExodus,Shemot\n").unwrap();
+        let mut library = Library::create().unwrap();
+        assert!(library.match_book("Bereshit").is_empty());
+        library.load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        let mut expected_return = BTreeSet::new();
+        expected_return.insert(BookId::Genesis);
+        assert_eq!(library.match_book("Bereshit"), expected_return);
+        assert_eq!(library.match_book("Gen"), expected_return);
+    }
+
+    #[test]
+    fn layered_unknown_book_test() {
+        let path = std::env::temp_dir().join(format!("scripcit_unknown_books_{}.csv", std::process::id()));
+        fs::write(&path, "Gospel of Thomas,Thomas
+").unwrap();
+        let mut library = Library::create().unwrap();
+        library.load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        let thomas = BookId::register("Gospel of Thomas");
+        assert_eq!(library.match_book("Thomas").into_iter().collect::<Vec<BookId>>(), vec![thomas]);
+        assert!(thomas > BookId::Revelation);
+        assert_eq!(thomas.to_string(), "Gospel of Thomas");
+    }
+
 }
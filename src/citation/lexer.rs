@@ -0,0 +1,209 @@
+//! Splitting a citation into typed tokens
+//!
+//! A citation like `II Sam. iv. 3-4` is read into the book's ordinal, the word of its name,
+//! the numbers and roman numerals of its address, and the separators and dashes between them.
+//! Verse parts written right after a verse, like the `b` of `4b`, and the following verses
+//! after a verse, like the `ff.` of `16ff.` or `16 ff.`, are suffixes of that verse.
+use std::iter::Peekable;
+use std::str::CharIndices;
+
+use super::address::{Following, VersePart};
+use super::roman_numerals;
+
+/// The kinds of tokens in a citation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenKind {
+    /// A word, like the name of a book
+    Word,
+    /// The number of a book, like the `1` of `1 John` or the `II` of `II Sam.`
+    Ordinal,
+    /// A chapter or verse in digits
+    Number,
+    /// A chapter or verse in roman numerals, like `iv`
+    Roman,
+    /// A `:`, `.`, `,` or `;` between the parts of an address
+    Separator,
+    /// A dash between the start and the end of a range
+    Dash,
+    /// A verse part or the following verses, written right after a verse
+    Suffix,
+    /// Anything that can not be part of a citation, like `16g` or `(`
+    Unknown,
+}
+
+/// A token of a citation, with where it starts in the citation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Token<'a> {
+    pub kind: TokenKind,
+    pub text: &'a str,
+    pub start: usize,
+}
+
+impl<'a> Token<'a> {
+    /// Where the token ends in the citation
+    pub fn end(&self) -> usize {
+        self.start + self.text.len()
+    }
+
+    /// Whether the token is a chapter or verse number
+    pub fn is_number(&self) -> bool {
+        self.kind == TokenKind::Number || self.kind == TokenKind::Roman
+    }
+
+    /// Whether the token is the given separator
+    pub fn is_separator(&self, separator: char) -> bool {
+        self.kind == TokenKind::Separator && self.text.starts_with(separator)
+    }
+}
+
+/// What a suffix says about its verse.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Suffix {
+    /// A part of the verse, like `b` or `aα`
+    Part(VersePart),
+    /// The verses after it, `f.` or `ff.`
+    Following(Following),
+}
+
+/// Reads a suffix, like `aα` or `ff.`, or None if it is not one
+pub fn suffix(text: &str) -> Option<Suffix> {
+    match text.trim_end_matches('.') {
+        "f" => return Some(Suffix::Following(Following::Verse)),
+        "ff" => return Some(Suffix::Following(Following::Verses)),
+        _ => (),
+    }
+    let mut chars = text.chars();
+    let part = chars.next().filter(|part| ('a'..='e').contains(part))?;
+    let sub_part = chars.next();
+    match (sub_part, chars.next()) {
+        (None, _) | (Some('α'..='δ'), None) => Some(Suffix::Part(VersePart { part, sub_part })),
+        _ => None,
+    }
+}
+
+/// Splits a citation into tokens. Whitespace only divides the tokens, and a number or roman
+/// numeral that starts the citation right before a word is the ordinal of a book.
+///
+/// # Examples
+///
+/// ```
+/// use scripcit::citation::lexer::{tokenize, TokenKind};
+///
+/// let kinds: Vec<TokenKind> = tokenize("II Sam. iv. 3-4b").iter().map(|token| token.kind).collect();
+/// assert_eq!(kinds, vec![
+///     TokenKind::Ordinal, TokenKind::Word, TokenKind::Separator, TokenKind::Roman, TokenKind::Separator,
+///     TokenKind::Number, TokenKind::Dash, TokenKind::Number, TokenKind::Suffix,
+/// ]);
+/// ```
+pub fn tokenize(text: &str) -> Vec<Token<'_>> {
+    let mut tokens: Vec<Token> = Vec::new();
+    let mut chars = text.char_indices().peekable();
+    while let Some((start, c)) = chars.next() {
+        let mut end = start + c.len_utf8();
+        let kind = match c {
+            c if c.is_whitespace() => continue,
+            ':' | '.' | ',' | ';' => TokenKind::Separator,
+            '-' | '–' | '—' => TokenKind::Dash,
+            c if c.is_ascii_digit() => {
+                take_while(&mut chars, &mut end, |c| c.is_ascii_digit());
+                let digits = end;
+                take_while(&mut chars, &mut end, char::is_alphabetic);
+                if end > digits {
+                    if let Some('.') = chars.peek().map(|(_, c)| *c).filter(|_| text[digits..end].starts_with('f')) {
+                        end += 1;
+                        chars.next();
+                    }
+                    if suffix(&text[digits..end]).is_none() {
+                        tokens.push(Token { kind: TokenKind::Unknown, text: &text[start..end], start });
+                        continue
+                    }
+                    tokens.push(Token { kind: TokenKind::Number, text: &text[start..digits], start });
+                    tokens.push(Token { kind: TokenKind::Suffix, text: &text[digits..end], start: digits });
+                    continue
+                }
+                TokenKind::Number
+            },
+            c if c.is_alphabetic() => {
+                take_while(&mut chars, &mut end, char::is_alphabetic);
+                let word = &text[start..end];
+                if matches!(word, "f" | "ff") && tokens.last().is_some_and(|token| token.is_number()) {
+                    // the following verses can also be written apart from their verse, as in `16 ff.`
+                    if let Some((_, '.')) = chars.peek() {
+                        end += 1;
+                        chars.next();
+                    }
+                    TokenKind::Suffix
+                } else if is_roman(word) {
+                    TokenKind::Roman
+                } else {
+                    TokenKind::Word
+                }
+            },
+            _ => TokenKind::Unknown,
+        };
+        if kind == TokenKind::Word && tokens.len() == 1 && tokens[0].is_number() {
+            tokens[0].kind = TokenKind::Ordinal;
+        }
+        tokens.push(Token { kind, text: &text[start..end], start });
+    }
+    tokens
+}
+
+/// Moves the end of the token past the characters that are kept
+fn take_while(chars: &mut Peekable<CharIndices>, end: &mut usize, keep: fn(char) -> bool) {
+    while let Some((i, c)) = chars.peek().copied().filter(|(_, c)| keep(*c)) {
+        *end = i + c.len_utf8();
+        chars.next();
+    }
+}
+
+/// Whether the word is a roman numeral written all in lower or all in upper case
+fn is_roman(word: &str) -> bool {
+    let lower = word.chars().all(|c| "ivxlc".contains(c));
+    let upper = word.chars().all(|c| "IVXLC".contains(c));
+    (lower || upper) && roman_numerals::is_roman_numeral(word)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn texts(text: &str) -> Vec<(TokenKind, &str)> {
+        tokenize(text).iter().map(|token| (token.kind, token.text)).collect()
+    }
+
+    #[test]
+    fn address_test() {
+        let tokens: Vec<&str> = tokenize("vi. 1; vii. 3").iter().map(|token| token.text).collect();
+        assert_eq!(tokens, vec!["vi", ".", "1", ";", "vii", ".", "3"]);
+    }
+
+    #[test]
+    fn book_test() {
+        assert_eq!(texts("1 John iii. 12"), vec![
+            (TokenKind::Ordinal, "1"), (TokenKind::Word, "John"), (TokenKind::Roman, "iii"),
+            (TokenKind::Separator, "."), (TokenKind::Number, "12"),
+        ]);
+        assert_eq!(texts("in 2:3")[0], (TokenKind::Word, "in"));
+        assert_eq!(texts("John 3:16 and")[3], (TokenKind::Number, "16"));
+        assert_eq!(texts("Mic. 4:5")[0], (TokenKind::Word, "Mic"));
+    }
+
+    #[test]
+    fn suffix_test() {
+        assert_eq!(texts("16ff. 21aα–26b"), vec![
+            (TokenKind::Number, "16"), (TokenKind::Suffix, "ff."), (TokenKind::Number, "21"),
+            (TokenKind::Suffix, "aα"), (TokenKind::Dash, "–"), (TokenKind::Number, "26"), (TokenKind::Suffix, "b"),
+        ]);
+        assert_eq!(texts("3:16g (4"), vec![
+            (TokenKind::Number, "3"), (TokenKind::Separator, ":"), (TokenKind::Unknown, "16g"),
+            (TokenKind::Unknown, "("), (TokenKind::Number, "4"),
+        ]);
+        assert_eq!(texts("16 ff."), vec![(TokenKind::Number, "16"), (TokenKind::Suffix, "ff.")]);
+        assert_eq!(suffix("f."), Some(Suffix::Following(Following::Verse)));
+        assert_eq!(suffix("aβγ"), None);
+        assert_eq!(suffix("16"), None);
+        assert_eq!(suffix("iii"), None);
+    }
+}
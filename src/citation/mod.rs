@@ -0,0 +1,13 @@
+//! Normalizing the scripture citations found in a text
+//!
+//! The book names are matched against the library of books, and the chapter and verse
+//! numbers are read into ranges of citations.
+
+pub mod address;
+pub mod book_id;
+pub mod book_linking;
+pub mod lexer;
+pub mod parser;
+pub mod ranges;
+#[path = "roman_numerals/lib.rs"] pub mod roman_numerals;
+pub mod versification;
@@ -0,0 +1,440 @@
+//! Reading the tokens of a citation with a grammar
+//!
+//! The scripture regexes still find where a citation might be in a text. The text of each
+//! match is split into tokens by the lexer, and read by this grammar in place of the old
+//! splitting of the book and its address with regexes:
+//!
+//! ```text
+//! citation  := book address
+//! book      := Ordinal? Word ("of" "the"? Word)* "."?
+//! address   := reference (Separator reference)* Separator?
+//! reference := chapter ("-" chapter (divider verse)?)?
+//!            | chapter divider verses
+//! verses    := verse ("-" verse (divider verse)?)?
+//! verse     := (Number | Roman) Suffix?
+//! ```
+//!
+//! The divider is the first `:`, `.` or `,` between a chapter and its verse, and the rest of
+//! the address has to use the same one. After a separator, a number is a new chapter when the
+//! divider comes after it, or when the reference before it had no verses, so `4:3, 5; 5:1` and
+//! `Ps. 120-134; 136` both read as expected. Otherwise it is another verse of the same chapter.
+use super::address::{CitationList, Diagnostic, Following, ScriptureCitation, VersePart};
+use super::book_id::BookId;
+use super::book_linking::Library;
+use super::lexer::{self, Suffix, Token, TokenKind};
+use super::roman_numerals;
+use super::versification::Scheme;
+
+/// Reads a whole citation, like `1 Cor. 4:3, 5, 6-7; 5:1-4`, into its list of citations. When
+/// the name could be more than one book, the best match whose chapters and verses are in the
+/// book is chosen. Citations that fall outside of the versification of their book are dropped,
+/// with a diagnostic for each.
+///
+/// # Examples
+///
+/// ```
+/// use scripcit::{Library, Scheme};
+/// use scripcit::citation::parser;
+///
+/// let library = Library::create().unwrap();
+/// let (scriptures, diagnostics) = parser::parse("1 Cor. 4:3, 5, 6-7; 5:1-4", &library, Scheme::Kjv);
+/// let crefs: Vec<String> = scriptures.scrip_vec.iter().map(|citation| citation.cref()).collect();
+/// assert_eq!(crefs, vec!["1 Corinthians 4:3", "1 Corinthians 4:5", "1 Corinthians 4:6-7", "1 Corinthians 5:1-4"]);
+/// assert!(diagnostics.is_empty());
+/// ```
+pub fn parse(text: &str, library: &Library, scheme: Scheme) -> (CitationList, Vec<Diagnostic>) {
+    let tokens = lexer::tokenize(text);
+    let (name, address) = match book(text, &tokens) {
+        Some(parts) => parts,
+        None => return (list(scheme, Vec::new()), vec![Diagnostic::UnknownBook(String::from(text))]),
+    };
+    let books = library.rank_books(name);
+    let mut readings = books.iter().map(|book| (*book, read_address(*book, text, address, library, scheme)));
+    let first = match readings.next() {
+        Some(first) => first,
+        None => return (list(scheme, Vec::new()), vec![Diagnostic::UnknownBook(String::from(name))]),
+    };
+    // the best match is passed over when its chapters and verses are not in the book
+    let possible = |(_, (_, diagnostics)): &(BookId, (CitationList, Vec<Diagnostic>))| {
+        !diagnostics.iter().any(|diagnostic| matches!(diagnostic, Diagnostic::ImpossibleRange { .. }))
+    };
+    let (book, (scriptures, mut address_diagnostics)) = if possible(&first) {
+        first
+    } else {
+        readings.find(possible).unwrap_or(first)
+    };
+    let mut diagnostics = Vec::new();
+    if books.len() > 1 {
+        let mut candidates = books.clone();
+        candidates.sort();
+        diagnostics.push(Diagnostic::AmbiguousBook { name: String::from(name), chosen: book, candidates });
+    }
+    diagnostics.append(&mut address_diagnostics);
+    (scriptures, diagnostics)
+}
+
+/// Reads an address that does not name its book, like `iv. 3-4`, into citations of the given book.
+///
+/// # Examples
+///
+/// ```
+/// use scripcit::{BookId, Library, Scheme};
+/// use scripcit::citation::parser;
+///
+/// let library = Library::create().unwrap();
+/// let (scriptures, _) = parser::parse_address(BookId::Romans, "3:27-4:2", &library, Scheme::Kjv);
+/// assert_eq!(scriptures.scrip_vec[0].cref(), "Romans 3:27-4:2");
+/// ```
+pub fn parse_address(book: BookId, address: &str, library: &Library, scheme: Scheme) -> (CitationList, Vec<Diagnostic>) {
+    read_address(book, address, &lexer::tokenize(address), library, scheme)
+}
+
+/// Splits the tokens into the name of the book and the tokens of its address, or None if
+/// the citation does not start with a book
+fn book<'t, 'a>(text: &'a str, tokens: &'t [Token<'a>]) -> Option<(&'a str, &'t [Token<'a>])> {
+    let first = tokens.first()?;
+    let word = if first.kind == TokenKind::Ordinal { 1 } else { 0 };
+    let mut name = tokens.get(word).filter(|token| matches!(token.kind, TokenKind::Word | TokenKind::Roman | TokenKind::Number))?;
+    let mut address = word + 1;
+    // the rest of a name of more than one word, like `Song of Songs` or `Acts of the Apostles`
+    while tokens.get(address).is_some_and(|token| token.text.eq_ignore_ascii_case("of")) {
+        let skip = if tokens.get(address + 1).is_some_and(|token| token.text.eq_ignore_ascii_case("the")) { 2 } else { 1 };
+        match tokens.get(address + skip).filter(|token| token.kind == TokenKind::Word) {
+            Some(word) => name = word,
+            None => break,
+        }
+        address += skip + 1;
+    }
+    if tokens.get(address).is_some_and(|token| token.is_separator('.')) {
+        address += 1;
+    }
+    Some((&text[first.start..name.end()], &tokens[address..]))
+}
+
+fn read_address(book: BookId, text: &str, tokens: &[Token], library: &Library, scheme: Scheme) -> (CitationList, Vec<Diagnostic>) {
+    let mut parser = Parser { text, tokens, position: 0, book, scheme, divider: None, citations: Vec::new(), diagnostics: Vec::new() };
+    parser.address(library.single_chapter(book));
+
+    let mut diagnostics = parser.diagnostics;
+    let mut citations = Vec::new();
+    for mut citation in parser.citations {
+        close_following(&mut citation, library);
+        match citation.validate(library) {
+            Ok(()) => citations.push(citation),
+            Err(reason) => diagnostics.push(Diagnostic::ImpossibleRange { citation, reason }),
+        }
+    }
+    (list(scheme, citations), diagnostics)
+}
+
+/// Ends `f.` on the last verse of its chapter at that verse, as in `John 3:36f.`, rather than
+/// on a verse the chapter does not have
+fn close_following(citation: &mut ScriptureCitation, library: &Library) {
+    if citation.following != Some(Following::Verse) || citation.scheme != Scheme::Kjv {
+        return
+    }
+    if let (Some(chapter), Some(start)) = (citation.start_chap, citation.start_verse) {
+        if library.verse_count(citation.book, chapter) == Some(start) {
+            citation.end_chap = None;
+            citation.end_verse = None;
+        }
+    }
+}
+
+fn list(scheme: Scheme, citations: Vec<ScriptureCitation>) -> CitationList {
+    let mut scriptures = CitationList::new();
+    scriptures.scheme = scheme;
+    scriptures.scrip_vec = citations;
+    scriptures
+}
+
+/// Reads the tokens of an address one reference at a time. Every rule returns None once
+/// the address could not be read, after recording the token that was wrong.
+struct Parser<'t, 'a> {
+    /// The text of the citation, for the diagnostics
+    text: &'a str,
+    tokens: &'t [Token<'a>],
+    position: usize,
+    book: BookId,
+    scheme: Scheme,
+    /// The separator between chapters and their verses, once the address has used one
+    divider: Option<char>,
+    citations: Vec<ScriptureCitation>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl<'t, 'a> Parser<'t, 'a> {
+    fn peek(&self, ahead: usize) -> Option<&'t Token<'a>> {
+        self.tokens.get(self.position + ahead)
+    }
+
+    /// Whether the next token is a number
+    fn number_ahead(&self, ahead: usize) -> bool {
+        self.peek(ahead).is_some_and(|token| token.is_number())
+    }
+
+    /// Whether the token can divide a chapter from its verse in this address
+    fn is_divider(&self, token: Option<&Token>) -> bool {
+        let separator = match token {
+            Some(token) if token.kind == TokenKind::Separator => token.text.chars().next().unwrap_or(';'),
+            _ => return false,
+        };
+        match self.divider {
+            Some(divider) => separator == divider,
+            None => separator != ';',
+        }
+    }
+
+    /// Takes the next token as the divider of the address
+    fn take_divider(&mut self) {
+        if let Some(token) = self.peek(0) {
+            self.divider = token.text.chars().next();
+        }
+        self.position += 1;
+    }
+
+    fn citation(&self, chapter: Option<i16>) -> ScriptureCitation {
+        let mut citation = ScriptureCitation::new(self.book, chapter);
+        citation.scheme = self.scheme;
+        citation
+    }
+
+    /// `address := reference (Separator reference)* Separator?`
+    fn address(&mut self, single_chapter: bool) -> Option<()> {
+        if self.tokens.iter().all(|token| token.kind == TokenKind::Separator) {
+            self.citations.push(self.citation(None));
+            return Some(())
+        }
+        if single_chapter && !self.names_chapter() {
+            // a lone number after a book with one chapter is a verse
+            self.divider = Some(':');
+            self.verses(self.citation(Some(1)))?;
+        } else {
+            self.reference()?;
+        }
+        while let Some(token) = self.peek(0) {
+            if token.kind != TokenKind::Separator {
+                return self.malformed(token)
+            }
+            self.position += 1;
+            match self.peek(0) {
+                Some(number) if number.is_number() => (),
+                Some(token) => return self.malformed(token),
+                None => break,
+            }
+            let last = self.citations.last()?;
+            if self.is_divider(self.peek(1)) || last.start_verse.is_none() && last.end_verse.is_none() {
+                self.reference()?;
+            } else {
+                let chapter = last.end_chap.or(last.start_chap);
+                self.verses(self.citation(chapter))?;
+            }
+        }
+        Some(())
+    }
+
+    /// Whether the address divides a chapter from a verse with a colon or a period, like `1:3`,
+    /// rather than only giving verses, like `3, 5`
+    fn names_chapter(&self) -> bool {
+        let last = self.tokens.len().saturating_sub(1);
+        self.tokens.iter().enumerate().any(|(i, token)| token.is_separator(':') || (token.is_separator('.') && i < last))
+    }
+
+    /// `reference := chapter ("-" chapter (divider verse)?)? | chapter divider verses`
+    fn reference(&mut self) -> Option<()> {
+        let chapter = self.number()?;
+        let mut citation = self.citation(Some(chapter));
+        // a chapter can be closed with a period before its range, as in `iii.-iv.`
+        if self.peek(0).is_some_and(|token| token.is_separator('.')) && self.peek(1).is_some_and(|token| token.kind == TokenKind::Dash) {
+            self.position += 1;
+        }
+        let next = self.peek(0);
+        if next.is_some_and(|token| token.kind == TokenKind::Dash) && self.number_ahead(1) {
+            self.position += 1;
+            citation.end_chap = Some(self.number()?);
+            // only a colon or a period ends a range of chapters with a verse, as in `3-4:5`
+            let divider = match (self.divider, self.peek(0)) {
+                (Some(_), token) => self.is_divider(token),
+                (None, Some(token)) => token.is_separator(':') || token.is_separator('.'),
+                (None, None) => false,
+            };
+            if divider && self.number_ahead(1) {
+                self.take_divider();
+                let (verse, suffix) = self.verse()?;
+                citation.end_verse = Some(verse);
+                citation.end_part = part(suffix);
+            }
+            self.citations.push(citation);
+            return Some(())
+        }
+        if self.is_divider(next) && self.number_ahead(1) {
+            self.take_divider();
+            return self.verses(citation)
+        }
+        self.citations.push(citation);
+        Some(())
+    }
+
+    /// `verses := verse ("-" verse (divider verse)?)?`
+    fn verses(&mut self, mut citation: ScriptureCitation) -> Option<()> {
+        let (verse, suffix) = self.verse()?;
+        citation.start_verse = Some(verse);
+        match suffix {
+            Some(Suffix::Following(following)) => {
+                citation.following = Some(following);
+                citation.end_chap = citation.start_chap;
+                if following == Following::Verse {
+                    match verse.checked_add(1) {
+                        Some(next) => citation.end_verse = Some(next),
+                        None => {
+                            // there is no verse after the largest number
+                            let element = String::from(self.tokens[self.position - 1].text);
+                            self.diagnostics.push(Diagnostic::MalformedAddress { citation: String::from(self.text), element });
+                            return None
+                        },
+                    }
+                }
+                self.citations.push(citation);
+                return Some(())
+            },
+            Some(Suffix::Part(verse_part)) => citation.start_part = Some(verse_part),
+            None => (),
+        }
+        if self.peek(0).is_some_and(|token| token.kind == TokenKind::Dash) && self.number_ahead(1) {
+            self.position += 1;
+            let (end, suffix) = self.verse()?;
+            if self.is_divider(self.peek(0)) && self.number_ahead(1) {
+                // a range across chapters, like `3:27-4:2`
+                self.position += 1;
+                citation.end_chap = Some(end);
+                let (end, suffix) = self.verse()?;
+                citation.end_verse = Some(end);
+                citation.end_part = part(suffix);
+            } else {
+                citation.end_chap = citation.start_chap;
+                citation.end_verse = Some(end);
+                citation.end_part = part(suffix);
+            }
+        }
+        self.citations.push(citation);
+        Some(())
+    }
+
+    /// `verse := (Number | Roman) Suffix?`
+    fn verse(&mut self) -> Option<(i16, Option<Suffix>)> {
+        let verse = self.number()?;
+        let suffix = match self.peek(0) {
+            Some(token) if token.kind == TokenKind::Suffix => {
+                self.position += 1;
+                lexer::suffix(token.text)
+            },
+            _ => None,
+        };
+        Some((verse, suffix))
+    }
+
+    /// Reads a chapter or verse number, in digits or roman numerals
+    fn number(&mut self) -> Option<i16> {
+        let token = self.peek(0)?;
+        let number = match token.kind {
+            TokenKind::Number => token.text.parse::<i16>().ok(),
+            TokenKind::Roman => Some(roman_numerals::convert_to_numbers(token.text)),
+            _ => None,
+        };
+        if number.is_none() {
+            self.diagnostics.push(Diagnostic::MalformedAddress { citation: String::from(self.text), element: String::from(token.text) });
+        }
+        self.position += 1;
+        number
+    }
+
+    /// Records a token that could not be read, which ends the address
+    fn malformed(&mut self, token: &Token) -> Option<()> {
+        self.diagnostics.push(Diagnostic::MalformedAddress { citation: String::from(self.text), element: String::from(token.text) });
+        None
+    }
+}
+
+/// The verse part of a suffix, if it is one
+fn part(suffix: Option<Suffix>) -> Option<VersePart> {
+    match suffix {
+        Some(Suffix::Part(verse_part)) => Some(verse_part),
+        _ => None,
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn crefs(text: &str) -> Vec<String> {
+        let library = Library::create().unwrap();
+        let (scriptures, _) = parse(text, &library, Scheme::Kjv);
+        scriptures.scrip_vec.iter().map(|citation| citation.cref()).collect()
+    }
+
+    #[test]
+    fn book_test() {
+        let text = "II Sam. iv. 3, 2";
+        let tokens = lexer::tokenize(text);
+        let (name, address) = book(text, &tokens).unwrap();
+        assert_eq!(name, "II Sam");
+        let address: Vec<&str> = address.iter().map(|token| token.text).collect();
+        assert_eq!(address, vec!["iv", ".", "3", ",", "2"]);
+        assert!(book("(3:4", &lexer::tokenize("(3:4")).is_none());
+    }
+
+    #[test]
+    fn long_book_test() {
+        assert_eq!(crefs("Song of Songs 2:1"), vec!["Song of Songs 2:1"]);
+        assert_eq!(crefs("Wisdom of Solomon 7:26"), vec!["Wisdom of Solomon 7:26"]);
+        assert_eq!(crefs("Acts of the Apostles 2:4"), vec!["Acts of the Apostles 2:4"]);
+        assert_eq!(crefs("Psalms of Solomon 17"), vec!["Psalms of Solomon 17"]);
+    }
+
+    #[test]
+    fn grammar_test() {
+        assert_eq!(crefs("II Sam. iv. 3, 2"), vec!["2 Samuel 4:3", "2 Samuel 4:2"]);
+        assert_eq!(crefs("Rom. 3:4, 6-8; 5; 6:1"), vec!["Romans 3:4", "Romans 3:6-8", "Romans 3:5", "Romans 6:1"]);
+        assert_eq!(crefs("Ps. 120-134; 136; 138"), vec!["Psalms 120-134", "Psalms 136", "Psalms 138"]);
+        assert_eq!(crefs("Rom 3, 5"), vec!["Romans 3:5"]);
+        assert_eq!(crefs("John 3:16f., 18ff."), vec!["John 3:16f.", "John 3:18ff."]);
+        assert_eq!(crefs("John 3:16 ff."), vec!["John 3:16ff."]);
+        assert_eq!(crefs("Rev. xii. 3."), vec!["Revelation 12:3"]);
+    }
+
+    #[test]
+    fn following_last_verse_test() {
+        let library = Library::create().unwrap();
+        let (scriptures, diagnostics) = parse("John 3:36f.", &library, Scheme::Kjv);
+        assert!(diagnostics.is_empty());
+        let citation = &scriptures.scrip_vec[0];
+        assert_eq!((citation.start_verse, citation.end_verse), (Some(36), None));
+        assert_eq!(citation.cref(), "John 3:36f.");
+    }
+
+    #[test]
+    fn unreadable_word_test() {
+        let library = Library::create().unwrap();
+        let (scriptures, diagnostics) = parse("John 3:16 and", &library, Scheme::Kjv);
+        assert_eq!(scriptures.scrip_vec[0].cref(), "John 3:16");
+        assert_eq!(diagnostics, vec![Diagnostic::MalformedAddress { citation: String::from("John 3:16 and"), element: String::from("and") }]);
+    }
+
+    #[test]
+    fn overflow_test() {
+        let library = Library::create().unwrap();
+        let (scriptures, diagnostics) = parse("John 3:32767f.", &library, Scheme::Kjv);
+        assert!(scriptures.scrip_vec.is_empty());
+        assert_eq!(diagnostics, vec![Diagnostic::MalformedAddress { citation: String::from("John 3:32767f."), element: String::from("f.") }]);
+    }
+
+    #[test]
+    fn malformed_test() {
+        let library = Library::create().unwrap();
+        let (scriptures, diagnostics) = parse("Rom. 3:4, 5 (6", &library, Scheme::Kjv);
+        assert_eq!(scriptures.scrip_vec.len(), 2);
+        assert_eq!(diagnostics, vec![Diagnostic::MalformedAddress { citation: String::from("Rom. 3:4, 5 (6"), element: String::from("(") }]);
+    }
+}
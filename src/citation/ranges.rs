@@ -0,0 +1,396 @@
+//! Sorting, merging and comparing citations as ranges of verses
+//!
+//! Each citation covers a range of places in its book, from a start chapter and verse to
+//! an end chapter and verse. A missing verse covers the whole chapter, and a missing chapter
+//! covers the whole book. Citations are only compared with citations of the same book
+//! in the same versification scheme.
+use std::cmp::Ordering;
+
+use super::address::{CitationList, Following, ScriptureCitation, VersePart};
+use super::book_id::BookId;
+use super::versification::Scheme;
+
+/// A place in a book, as a chapter, a verse and a part of the verse. `OPEN` stands for the
+/// end of a chapter, or the last chapter of a book. Parts are ranked from 1, with four
+/// Greek sub-parts to each letter, so the whole verse starts at 0 and ends at `WHOLE`.
+type Place = (i16, i16, u8);
+
+const OPEN: i16 = i16::MAX;
+
+const WHOLE: u8 = u8::MAX;
+
+impl ScriptureCitation {
+    /// The first and last place of the citation
+    fn bounds(&self) -> (Place, Place) {
+        let start_chap = match self.start_chap {
+            Some(start_chap) => start_chap,
+            None => return ((1, 1, 0), (OPEN, OPEN, WHOLE)),
+        };
+        let start = (start_chap, self.start_verse.unwrap_or(1), start_rank(self.start_part));
+        let end = match (self.end_chap, self.end_verse) {
+            (Some(end_chap), end_verse) => (end_chap, end_verse.unwrap_or(OPEN), end_rank(self.end_part)),
+            (None, Some(end_verse)) => (start_chap, end_verse, end_rank(self.end_part)),
+            (None, None) => (start_chap, self.start_verse.unwrap_or(OPEN), end_rank(self.start_part)),
+        };
+        (start, end)
+    }
+
+    /// Builds the citation that covers the places from start to end
+    fn from_bounds(book: BookId, scheme: Scheme, start: Place, end: Place) -> ScriptureCitation {
+        let mut citation = ScriptureCitation::new(book, None);
+        citation.scheme = scheme;
+        if start == (1, 1, 0) && end == (OPEN, OPEN, WHOLE) {
+            return citation
+        }
+        citation.start_chap = Some(start.0);
+        let whole_chapters = start.1 == 1 && start.2 == 0 && end.1 == OPEN;
+        if !whole_chapters {
+            citation.start_verse = Some(start.1);
+            citation.start_part = start_part(start.2);
+        }
+        if end.0 == start.0 && end.1 == start.1 {
+            if start.2 == 0 && end.2 == WHOLE {
+                return citation
+            }
+            if let Some(part) = single_part(start.2, end.2) {
+                citation.start_part = Some(part);
+                return citation
+            }
+        }
+        if !(whole_chapters && end.0 == start.0) {
+            citation.end_chap = Some(end.0);
+            if end.1 != OPEN {
+                citation.end_verse = Some(end.1);
+                citation.end_part = end_part(end.2);
+            } else if end.0 == start.0 {
+                citation.following = Some(Following::Verses);
+            }
+        }
+        citation
+    }
+
+    /// Whether both citations point into the same book, in the same scheme
+    fn same_book(&self, other: &ScriptureCitation) -> bool {
+        self.book == other.book && self.scheme == other.scheme
+    }
+
+    /// Orders citations in canonical book order, then by where they start and end
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scripcit::{BookId, ScriptureCitation};
+    ///
+    /// let romans = ScriptureCitation::new(BookId::Romans, Some(3));
+    /// let genesis = ScriptureCitation::new(BookId::Genesis, Some(50));
+    /// assert!(genesis.canonical_cmp(&romans).is_lt());
+    /// ```
+    pub fn canonical_cmp(&self, other: &ScriptureCitation) -> Ordering {
+        self.book.cmp(&other.book)
+            .then((self.scheme as u8).cmp(&(other.scheme as u8)))
+            .then(self.bounds().cmp(&other.bounds()))
+    }
+
+    /// Whether every verse of the other citation is in this citation
+    pub fn contains(&self, other: &ScriptureCitation) -> bool {
+        let (start, end) = self.bounds();
+        let (other_start, other_end) = other.bounds();
+        self.same_book(other) && start <= other_start && other_end <= end
+    }
+
+    /// Whether the citations have at least one verse in common
+    pub fn overlaps(&self, other: &ScriptureCitation) -> bool {
+        let (start, end) = self.bounds();
+        let (other_start, other_end) = other.bounds();
+        self.same_book(other) && start <= other_end && other_start <= end
+    }
+
+    /// The verses the citations have in common, if they overlap
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scripcit::{BookId, ScriptureCitation};
+    ///
+    /// let mut first = ScriptureCitation::new(BookId::Romans, Some(3));
+    /// first.start_verse = Some(21);
+    /// first.end_verse = Some(26);
+    /// let mut second = ScriptureCitation::new(BookId::Romans, Some(3));
+    /// second.start_verse = Some(25);
+    /// second.end_chap = Some(4);
+    /// second.end_verse = Some(3);
+    /// assert_eq!(first.intersection(&second).unwrap().cref(), "Romans 3:25-26");
+    /// ```
+    pub fn intersection(&self, other: &ScriptureCitation) -> Option<ScriptureCitation> {
+        if !self.overlaps(other) {
+            return None
+        }
+        let (start, end) = self.bounds();
+        let (other_start, other_end) = other.bounds();
+        Some(ScriptureCitation::from_bounds(self.book, self.scheme, start.max(other_start), end.min(other_end)))
+    }
+}
+
+/// The place right after the given one. Without the versification the last verse of
+/// a chapter is not known, so only the open end of a chapter runs into the next chapter.
+/// A verse runs into the first part of the next verse as well as the whole of it.
+fn next_place(place: Place) -> Place {
+    match place {
+        (OPEN, _, _) => (OPEN, OPEN, WHOLE),
+        (chapter, OPEN, _) => (chapter + 1, 1, 1),
+        (chapter, verse, WHOLE) => (chapter, verse + 1, 1),
+        (chapter, verse, rank) => (chapter, verse, rank + 1),
+    }
+}
+
+/// The rank of the letter and the sub-part of a verse part, both counted from 0. Letters
+/// outside of `a` to `e` are ranked as the nearest of them.
+fn part_rank(part: VersePart, sub_part: u8) -> u8 {
+    let letter = (part.part as u32).saturating_sub('a' as u32).min(4) as u8;
+    letter * 4 + sub_part + 1
+}
+
+/// The rank where a verse part starts, or 0 for the whole verse
+fn start_rank(part: Option<VersePart>) -> u8 {
+    match part {
+        Some(part) => part_rank(part, part.sub_part.map(greek_index).unwrap_or(0)),
+        None => 0,
+    }
+}
+
+/// The rank where a verse part ends, or `WHOLE` for the whole verse
+fn end_rank(part: Option<VersePart>) -> u8 {
+    match part {
+        Some(part) => part_rank(part, part.sub_part.map(greek_index).unwrap_or(3)),
+        None => WHOLE,
+    }
+}
+
+/// The index of a Greek sub-part, from 0 for `α` to 3 for `δ`, with any other letter 
+/// ranked as the nearest of them
+fn greek_index(sub_part: char) -> u8 {
+    (sub_part as u32).saturating_sub('α' as u32).min(3) as u8
+}
+
+/// The verse part of a rank, with its sub-part unless it is the given one
+fn rank_part(rank: u8, whole: u8) -> VersePart {
+    let (letter, sub_part) = ((rank - 1) / 4, (rank - 1) % 4);
+    VersePart {
+        part: (b'a' + letter) as char,
+        sub_part: if sub_part == whole { None } else { std::char::from_u32('α' as u32 + sub_part as u32) },
+    }
+}
+
+fn start_part(rank: u8) -> Option<VersePart> {
+    if rank == 0 { None } else { Some(rank_part(rank, 0)) }
+}
+
+fn end_part(rank: u8) -> Option<VersePart> {
+    if rank == WHOLE { None } else { Some(rank_part(rank, 3)) }
+}
+
+/// The one verse part that runs between the ranks, like `b` or `aβ`, if there is one
+fn single_part(start: u8, end: u8) -> Option<VersePart> {
+    if start == 0 || end == WHOLE || (start - 1) / 4 != (end - 1) / 4 {
+        return None
+    }
+    if start == end {
+        return Some(rank_part(start, 4))
+    }
+    if start_part(start).is_some_and(|part| part.sub_part.is_none()) && end_part(end).is_some_and(|part| part.sub_part.is_none()) {
+        return Some(rank_part(start, 0))
+    }
+    None
+}
+
+impl CitationList {
+    /// Sorts the citations in canonical order
+    pub fn sort(&mut self) {
+        self.scrip_vec.sort_by(|a, b| a.canonical_cmp(b));
+    }
+
+    /// Sorts the citations in canonical order and removes the repeated ones. The first
+    /// of the repeated citations is kept.
+    pub fn dedup(&mut self) {
+        self.sort();
+        self.scrip_vec.dedup_by(|a, b| a.same_book(b) && a.bounds() == b.bounds());
+    }
+
+    /// Sorts the citations in canonical order and merges the ones that overlap or follow
+    /// each other, so `Rom 3:21-24` and `Rom 3:25` become `Rom 3:21-25`. A merged citation
+    /// keeps the span of the first citation in it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scripcit::{CitationList, Library};
+    ///
+    /// let library = Library::create().unwrap();
+    /// let mut scriptures = CitationList::new();
+    /// scriptures.insert("Rom. 3:25", &library);
+    /// scriptures.insert("Gen. 1:1", &library);
+    /// scriptures.insert("Rom. 3:21-24", &library);
+    /// scriptures.merge();
+    /// let merged: Vec<String> = scriptures.scrip_vec.iter().map(|c| c.cref()).collect();
+    /// assert_eq!(merged, vec!["Genesis 1:1", "Romans 3:21-25"]);
+    /// ```
+    pub fn merge(&mut self) {
+        self.sort();
+        let mut merged: Vec<ScriptureCitation> = Vec::new();
+        for citation in self.scrip_vec.drain(..) {
+            if let Some(last) = merged.last_mut() {
+                let (start, end) = last.bounds();
+                let (next_start, next_end) = citation.bounds();
+                if last.same_book(&citation) && next_start <= next_place(end) {
+                    let span = last.span;
+                    *last = ScriptureCitation::from_bounds(last.book, last.scheme, start, end.max(next_end));
+                    last.span = span;
+                    continue
+                }
+            }
+            merged.push(citation);
+        }
+        self.scrip_vec = merged;
+    }
+
+    /// A new list with the citations of both lists, merged
+    pub fn union(&self, other: &CitationList) -> CitationList {
+        let mut union = CitationList::new();
+        union.scheme = self.scheme;
+        union.scrip_vec = self.scrip_vec.iter().chain(other.scrip_vec.iter()).cloned().collect();
+        union.merge();
+        union
+    }
+
+    /// A new list with the verses that are cited in both lists, merged
+    pub fn intersection(&self, other: &CitationList) -> CitationList {
+        let mut intersection = CitationList::new();
+        intersection.scheme = self.scheme;
+        intersection.scrip_vec = self.scrip_vec.iter()
+            .flat_map(|citation| other.scrip_vec.iter().filter_map(move |other| citation.intersection(other)))
+            .collect();
+        intersection.merge();
+        intersection
+    }
+
+    /// Whether every verse of the citation is cited in the list
+    pub fn contains(&self, citation: &ScriptureCitation) -> bool {
+        let mut merged = CitationList::new();
+        merged.scrip_vec = self.scrip_vec.clone();
+        merged.merge();
+        merged.scrip_vec.iter().any(|range| range.contains(citation))
+    }
+
+    /// Whether any verse of the citation is cited in the list
+    pub fn overlaps(&self, citation: &ScriptureCitation) -> bool {
+        self.scrip_vec.iter().any(|range| range.overlaps(citation))
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::citation::book_linking::Library;
+
+    fn citations(texts: &[&str]) -> CitationList {
+        let library = Library::create().unwrap();
+        let mut scriptures = CitationList::new();
+        for text in texts {
+            scriptures.insert(text, &library);
+        }
+        scriptures
+    }
+
+    fn range(book: BookId, start_chap: i16, start_verse: i16, end_chap: i16, end_verse: i16) -> ScriptureCitation {
+        let mut citation = ScriptureCitation::new(book, Some(start_chap));
+        citation.start_verse = Some(start_verse);
+        citation.end_chap = Some(end_chap);
+        citation.end_verse = Some(end_verse);
+        citation
+    }
+
+    fn crefs(scriptures: &CitationList) -> Vec<String> {
+        scriptures.scrip_vec.iter().map(|citation| citation.cref()).collect()
+    }
+
+    #[test]
+    fn bounds_test() {
+        let mut scriptures = citations(&["Rom. 3:21-24", "Rom. 3", "Rom. 3-4", "Rom. 3:21", "Rom. 3:21a-26b", "Rom. 3:21b", "Gen. 12:3aα"]);
+        scriptures.scrip_vec.push(range(BookId::Romans, 3, 21, 4, 2));
+        for citation in &scriptures.scrip_vec {
+            let (start, end) = citation.bounds();
+            assert_eq!(&ScriptureCitation::from_bounds(citation.book, citation.scheme, start, end), citation);
+        }
+        let whole_book = ScriptureCitation::new(BookId::Jude, None);
+        assert_eq!(whole_book.bounds(), ((1, 1, 0), (OPEN, OPEN, WHOLE)));
+    }
+
+    #[test]
+    fn sort_test() {
+        let mut scriptures = citations(&["Rev. 1:1", "Rom. 3:25", "Gen. 2:1", "Rom. 3:21-24", "Rom. 2"]);
+        scriptures.sort();
+        assert_eq!(crefs(&scriptures), vec!["Genesis 2:1", "Romans 2", "Romans 3:21-24", "Romans 3:25", "Revelation 1:1"]);
+    }
+
+    #[test]
+    fn dedup_test() {
+        let mut scriptures = citations(&["Rom. 3:25", "Gen. 2:1", "Romans iii. 25", "Gen. 2:1"]);
+        scriptures.dedup();
+        assert_eq!(crefs(&scriptures), vec!["Genesis 2:1", "Romans 3:25"]);
+    }
+
+    #[test]
+    fn merge_test() {
+        let mut scriptures = citations(&["Rom. 3:21-24", "Rom. 3:25", "Rom. 3:28", "Rom. 5", "Rom. 6:1", "Gal. 1:1"]);
+        scriptures.scrip_vec.push(range(BookId::Romans, 3, 27, 4, 2));
+        scriptures.merge();
+        assert_eq!(crefs(&scriptures), vec!["Romans 3:21-25", "Romans 3:27-4:2", "Romans 5:1-6:1", "Galatians 1:1"]);
+
+        let mut chapters = citations(&["Rom. 3", "Rom. 3:4", "Rom. 4"]);
+        chapters.merge();
+        assert_eq!(crefs(&chapters), vec!["Romans 3-4"]);
+    }
+
+    #[test]
+    fn verse_part_test() {
+        let mut scriptures = citations(&["Rom. 3:21b", "Rom. 3:21a", "Rom. 3:20", "Rom. 3:22aβ", "Rom. 3:22aα"]);
+        scriptures.sort();
+        assert_eq!(crefs(&scriptures), vec!["Romans 3:20", "Romans 3:21a", "Romans 3:21b", "Romans 3:22aα", "Romans 3:22aβ"]);
+        scriptures.scrip_vec.truncate(3);
+        scriptures.merge();
+        assert_eq!(crefs(&scriptures), vec!["Romans 3:20-21b"]);
+
+        let verse = &citations(&["Rom. 3:21"]).scrip_vec[0];
+        let part = &citations(&["Rom. 3:21b"]).scrip_vec[0];
+        assert!(verse.contains(part));
+        assert!(!part.contains(verse));
+        assert_eq!(verse.intersection(part).as_ref(), Some(part));
+
+        let mut odd = verse.clone();
+        odd.start_part = Some(VersePart { part: 'A', sub_part: None });
+        odd.end_verse = Some(21);
+        odd.end_part = Some(VersePart { part: 'z', sub_part: Some('ω') });
+        assert!(verse.contains(&odd));
+        assert!(part.overlaps(&odd));
+    }
+
+    #[test]
+    fn set_test() {
+        let first = citations(&["Rom. 3:21-26", "Gal. 2:16"]);
+        let mut second = citations(&["Gal. 3:1"]);
+        second.scrip_vec.push(range(BookId::Romans, 3, 25, 4, 3));
+        assert_eq!(crefs(&first.union(&second)), vec!["Romans 3:21-4:3", "Galatians 2:16", "Galatians 3:1"]);
+        assert_eq!(crefs(&first.intersection(&second)), vec!["Romans 3:25-26"]);
+
+        let verse = &citations(&["Rom. 3:23"]).scrip_vec[0];
+        assert!(first.contains(verse));
+        assert!(first.overlaps(verse));
+        let across = range(BookId::Romans, 3, 26, 4, 1);
+        assert!(!first.contains(&across));
+        assert!(first.overlaps(&across));
+        assert!(first.union(&second).contains(&across));
+        let other_book = &citations(&["Gal. 3:23"]).scrip_vec[0];
+        assert!(!first.overlaps(other_book));
+    }
+}
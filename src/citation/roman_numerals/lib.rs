@@ -22,7 +22,7 @@ pub fn is_roman_numeral(numeral: &str) -> bool {
             // r"(?i)^([MDCLXVI])M*(C[MD]|D?C{0,3})(X[CL]|L?X{0,3})(I[XV]|V?I{0,3})$").unwrap();
             r"(?i)^(M{0,3})(D?C{0,3}|CM|CD)(L?X{0,3}|XC|XL)(V?I{0,3}|IX|IV)$").unwrap();
     }
-    NUMERAL_RE.is_match(numeral)
+    !numeral.is_empty() && NUMERAL_RE.is_match(numeral)
 }
 
 /// This function converts roman numerals to i16 numbers, striping white space
@@ -40,6 +40,7 @@ pub fn convert_to_numbers(numeral: &str) -> i16 {
 }
 
 #[cfg(test)]
+#[allow(clippy::bool_assert_comparison, clippy::needless_borrow)]
 mod tests {
     use super::*;
 
@@ -60,6 +61,11 @@ mod tests {
         let lower_numeral = "XM";
         assert_eq!(is_roman_numeral(lower_numeral), false);
     }
+    #[test]
+    fn empty_is_not_roman_test() {
+        assert!(!is_roman_numeral(""));
+    }
+
     #[test]
     fn pure_roman_numeral() {
         let roman_numeral = "CMXC";
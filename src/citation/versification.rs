@@ -0,0 +1,225 @@
+//! Converting chapter and verse numbers between versification schemes.
+//!
+//! Bibles do not agree on where chapters and verses start. The Hebrew text counts psalm
+//! titles as verses, the Septuagint and Vulgate number the psalms differently, and
+//! books like Joel and Malachi break their chapters in different places. Every scheme
+//! is mapped onto the common English (KJV) numbering, which is used as the pivot when
+//! converting between any two schemes.
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+use serde::Serialize;
+
+use super::book_id::BookId;
+
+/// The differences between each scheme and the English numbering. Each line is the
+/// scheme, the canonical book, the chapter, the first and last verse of a block of
+/// verses, and the English chapter and verse that the block starts on.
+const VERSIFICATION_CSV: &str = include_str!("../../data/versification.csv");
+
+/// The versification schemes that citations can be numbered in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize)]
+pub enum Scheme {
+    /// The common English numbering of the King James Version
+    #[default]
+    #[serde(rename = "kjv")]
+    Kjv,
+    /// The numbering of the Hebrew Masoretic Text
+    #[serde(rename = "mt")]
+    Hebrew,
+    /// The numbering of the Greek Septuagint, with the Byzantine New Testament
+    #[serde(rename = "lxx")]
+    Septuagint,
+    /// The numbering of the Latin Vulgate
+    #[serde(rename = "vulgate")]
+    Vulgate,
+}
+
+impl fmt::Display for Scheme {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Scheme::Kjv => "kjv",
+            Scheme::Hebrew => "mt",
+            Scheme::Septuagint => "lxx",
+            Scheme::Vulgate => "vulgate",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for Scheme {
+    type Err = String;
+
+    fn from_str(scheme: &str) -> Result<Scheme, String> {
+        match scheme.to_lowercase().as_str() {
+            "kjv" | "english" | "en" => Ok(Scheme::Kjv),
+            "mt" | "hebrew" | "bhs" => Ok(Scheme::Hebrew),
+            "lxx" | "septuagint" => Ok(Scheme::Septuagint),
+            "vulgate" | "vulg" | "vg" => Ok(Scheme::Vulgate),
+            _ => Err(format!("unknown versification scheme: {}", scheme)),
+        }
+    }
+}
+
+/// A block of verses in one scheme, and where it starts in the English numbering.
+struct Mapping {
+    scheme: Scheme,
+    book: BookId,
+    chapter: i16,
+    first_verse: i16,
+    last_verse: i16,
+    kjv_chapter: i16,
+    kjv_verse: i16,
+}
+
+impl Mapping {
+    /// The last English verse covered by the block
+    fn kjv_last_verse(&self) -> i16 {
+        self.kjv_verse + self.last_verse - self.first_verse
+    }
+}
+
+/// All of the mappings between the schemes and the English numbering.
+pub struct Versification {
+    mappings: Vec<Mapping>,
+}
+
+impl Versification {
+    /// Creates the versification mappings from the data set
+    pub fn create() -> Result<Versification, Box<dyn Error>> {
+        let mut mappings = Vec::new();
+        for line in VERSIFICATION_CSV.lines() {
+            let fields: Vec<&str> = line.split(',').map(|field| field.trim()).collect();
+            if fields.len() != 7 {
+                continue
+            }
+            let numbers = fields[2..].iter()
+                .map(|field| field.parse::<i16>())
+                .collect::<Result<Vec<i16>, _>>()?;
+            mappings.push(Mapping {
+                scheme: fields[0].parse()?,
+                book: fields[1].parse()?,
+                chapter: numbers[0],
+                first_verse: numbers[1],
+                last_verse: numbers[2],
+                kjv_chapter: numbers[3],
+                kjv_verse: numbers[4],
+            });
+        }
+        Ok(Versification { mappings })
+    }
+
+    /// An empty set of mappings, where every scheme is numbered like the English.
+    pub fn new() -> Versification {
+        Versification { mappings: Vec::new() }
+    }
+
+    /// Converts a chapter and verse from one scheme to another. A missing verse
+    /// converts the chapter as a whole. Places that are numbered the same in both
+    /// schemes are returned unchanged.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scripcit::citation::book_id::BookId;
+    /// use scripcit::citation::versification::{Scheme, Versification};
+    /// 
+    /// let versification = Versification::create().unwrap();
+    /// let english = versification.convert(BookId::Malachi, 3, Some(20), Scheme::Hebrew, Scheme::Kjv);
+    /// assert_eq!(english, (4, Some(2)));
+    /// ```
+    pub fn convert(&self, book: BookId, chapter: i16, verse: Option<i16>, from: Scheme, to: Scheme) -> (i16, Option<i16>) {
+        if from == to {
+            return (chapter, verse)
+        }
+        let (kjv_chapter, kjv_verse) = self.to_kjv(book, chapter, verse, from);
+        self.kjv_to(book, kjv_chapter, kjv_verse, to)
+    }
+
+    /// Converts a place in the given scheme to the English numbering
+    fn to_kjv(&self, book: BookId, chapter: i16, verse: Option<i16>, scheme: Scheme) -> (i16, Option<i16>) {
+        let mut blocks = self.mappings.iter()
+            .filter(|mapping| mapping.scheme == scheme && mapping.book == book && mapping.chapter == chapter);
+        match verse {
+            Some(verse) => blocks
+                .find(|mapping| mapping.first_verse <= verse && verse <= mapping.last_verse)
+                .map(|mapping| (mapping.kjv_chapter, Some(mapping.kjv_verse + verse - mapping.first_verse)))
+                .unwrap_or((chapter, Some(verse))),
+            None => blocks.next()
+                .map(|mapping| (mapping.kjv_chapter, None))
+                .unwrap_or((chapter, None)),
+        }
+    }
+
+    /// Converts a place in the English numbering to the given scheme
+    fn kjv_to(&self, book: BookId, chapter: i16, verse: Option<i16>, scheme: Scheme) -> (i16, Option<i16>) {
+        let mut blocks = self.mappings.iter()
+            .filter(|mapping| mapping.scheme == scheme && mapping.book == book && mapping.kjv_chapter == chapter);
+        match verse {
+            Some(verse) => blocks
+                .find(|mapping| mapping.kjv_verse <= verse && verse <= mapping.kjv_last_verse())
+                .map(|mapping| (mapping.chapter, Some(mapping.first_verse + verse - mapping.kjv_verse)))
+                .unwrap_or((chapter, Some(verse))),
+            None => blocks.next()
+                .map(|mapping| (mapping.chapter, None))
+                .unwrap_or((chapter, None)),
+        }
+    }
+}
+
+impl Default for Versification {
+    fn default() -> Versification {
+        Versification::new()
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scheme_from_str_test() {
+        assert_eq!("lxx".parse::<Scheme>(), Ok(Scheme::Septuagint));
+        assert_eq!("KJV".parse::<Scheme>(), Ok(Scheme::Kjv));
+        assert_eq!("Vulgate".parse::<Scheme>(), Ok(Scheme::Vulgate));
+        assert!("nrsv".parse::<Scheme>().is_err());
+    }
+
+    #[test]
+    fn malachi_test() {
+        let versification = Versification::create().unwrap();
+        assert_eq!(versification.convert(BookId::Malachi, 3, Some(19), Scheme::Hebrew, Scheme::Kjv), (4, Some(1)));
+        assert_eq!(versification.convert(BookId::Malachi, 4, Some(6), Scheme::Kjv, Scheme::Hebrew), (3, Some(24)));
+        assert_eq!(versification.convert(BookId::Malachi, 3, Some(18), Scheme::Hebrew, Scheme::Kjv), (3, Some(18)));
+    }
+
+    #[test]
+    fn joel_test() {
+        let versification = Versification::create().unwrap();
+        assert_eq!(versification.convert(BookId::Joel, 3, Some(1), Scheme::Hebrew, Scheme::Kjv), (2, Some(28)));
+        assert_eq!(versification.convert(BookId::Joel, 3, Some(5), Scheme::Kjv, Scheme::Hebrew), (4, Some(5)));
+        assert_eq!(versification.convert(BookId::Joel, 2, Some(28), Scheme::Kjv, Scheme::Septuagint), (3, Some(1)));
+    }
+
+    #[test]
+    fn psalm_test() {
+        let versification = Versification::create().unwrap();
+        // Psalm 23 is Psalm 22 in the Greek and Latin
+        assert_eq!(versification.convert(BookId::Psalms, 22, Some(1), Scheme::Septuagint, Scheme::Kjv), (23, Some(1)));
+        assert_eq!(versification.convert(BookId::Psalms, 23, None, Scheme::Kjv, Scheme::Vulgate), (22, None));
+        // Psalm 51 has a two verse title in the Hebrew, Greek and Latin
+        assert_eq!(versification.convert(BookId::Psalms, 51, Some(1), Scheme::Kjv, Scheme::Hebrew), (51, Some(3)));
+        assert_eq!(versification.convert(BookId::Psalms, 50, Some(3), Scheme::Vulgate, Scheme::Hebrew), (51, Some(3)));
+        // Psalms 9 and 10 are one psalm in the Greek
+        assert_eq!(versification.convert(BookId::Psalms, 9, Some(22), Scheme::Septuagint, Scheme::Kjv), (10, Some(1)));
+        assert_eq!(versification.convert(BookId::Psalms, 147, Some(12), Scheme::Kjv, Scheme::Septuagint), (147, Some(1)));
+    }
+
+    #[test]
+    fn romans_doxology_test() {
+        let versification = Versification::create().unwrap();
+        assert_eq!(versification.convert(BookId::Romans, 16, Some(25), Scheme::Kjv, Scheme::Septuagint), (14, Some(24)));
+        assert_eq!(versification.convert(BookId::Romans, 14, Some(26), Scheme::Septuagint, Scheme::Vulgate), (16, Some(27)));
+    }
+}
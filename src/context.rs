@@ -0,0 +1,263 @@
+//! Reading citations in the context of the text around them
+//!
+//! Authors often leave out the book when they keep citing the same one, as in
+//! "Gen. 1:1 … and again in 2:3". These addresses are found on their own, and given the
+//! book of the closest citation before them that named one. Commentaries also point back
+//! to the last citation with "ver. 44", "vv. 3-5", "ibid." or "in the same chapter", which
+//! are expanded into whole citations.
+use regex::Regex;
+
+use crate::citation::address::{CitationList, Diagnostic, ScriptureCitation};
+use crate::citation::book_id::BookId;
+use crate::citation::book_linking::Library;
+use crate::citation::versification::Scheme;
+use crate::scriptureregex;
+use crate::span::LineIndex;
+use crate::Found;
+
+/// Finds the addresses in the text that do not name their book, and gives them the book
+/// of the last match before them, if that match ends at most `window` characters before
+/// them. Their citations are marked as inferred, and they are added to the matches in
+/// the order of the text.
+///
+/// The scripture regex reads the word before a lone address as its book, as in `in 2:3`.
+/// Matches whose book is an unknown word in lower case are replaced by the address in
+/// them, while a match with any other unknown book ends the current book.
+///
+/// # Examples
+///
+/// ```
+/// use scripcit::{Library, Scheme};
+///
+/// let text = "Gen. 1:1 and again in 2:3";
+/// let library = Library::create().unwrap();
+/// let found = scripcit::extract_with(text, &library, Scheme::Kjv);
+/// let found = scripcit::context::carry_books(text, found, &library, 100);
+/// assert_eq!(found[1].citations[0].cref(), "Genesis 2:3");
+/// assert!(found[1].citations[0].inferred);
+/// ```
+pub fn carry_books(text: &str, found: Vec<Found>, library: &Library, window: usize) -> Vec<Found> {
+    lazy_static! {
+        static ref ADDRESS_REGEX: Regex = Regex::new(&scriptureregex::address_regex_creator()).unwrap();
+    }
+    let line_index = LineIndex::new(text);
+    let mut carried = Vec::new();
+    let mut replaced = Vec::new();
+    let mut next = 0;
+    let mut current: Option<(BookId, Scheme, usize)> = None;
+    for mat in ADDRESS_REGEX.find_iter(text) {
+        let mut word = None;
+        while next < found.len() && found[next].span.start < mat.end() {
+            let previous = &found[next];
+            if is_word(previous) {
+                if previous.span.end > mat.start() {
+                    word = Some(next);
+                }
+            } else {
+                current = previous.citations.last().map(|citation| (citation.book, citation.scheme, previous.span.end));
+            }
+            next += 1;
+        }
+        let (book, scheme, end) = match current {
+            Some(current) if current.2 <= mat.start() => current,
+            _ => continue,
+        };
+        if text[end..mat.start()].chars().count() > window {
+            continue
+        }
+        let span = line_index.span(mat.start(), mat.end());
+        let mut scriptures = CitationList::new();
+        scriptures.scheme = scheme;
+        let diagnostics = scriptures.insert_address(book, mat.as_str(), library);
+        for citation in scriptures.scrip_vec.iter_mut() {
+            citation.span = Some(span);
+            citation.inferred = true;
+        }
+        replaced.extend(word);
+        carried.push(Found { source: None, page: None, position: None, text: String::from(mat.as_str()), span, citations: scriptures.scrip_vec, diagnostics });
+    }
+
+    let mut found: Vec<Found> = found.into_iter()
+        .enumerate()
+        .filter(|(i, _)| !replaced.contains(i))
+        .map(|(_, mat)| mat)
+        .collect();
+    found.append(&mut carried);
+    found.sort_by_key(|mat| mat.span.start);
+    found
+}
+
+/// Expands the references back to the last citation in the text, like `ver. 44`, `vv. 3-5`,
+/// `ibid.` and `in the same chapter`, into whole citations. The last citation is the last one
+/// with a book and a chapter, including the ones expanded before it. The expanded citations
+/// are marked as inferred, and are added to the matches in the order of the text, replacing
+/// the matches without a citation that overlap them.
+///
+/// # Examples
+///
+/// ```
+/// let text = "see John viii. 44, and the words in ver. 40";
+/// let found = scripcit::context::resolve_relative(text, scripcit::extract(text), &scripcit::Library::create().unwrap());
+/// assert_eq!(found[1].text, "ver. 40");
+/// assert_eq!(found[1].citations[0].cref(), "John 8:40");
+/// ```
+pub fn resolve_relative(text: &str, found: Vec<Found>, library: &Library) -> Vec<Found> {
+    lazy_static! {
+        static ref RELATIVE_REGEX: Regex = Regex::new(r"(?i)\b(?:(?P<ibid>ibid\b\.?)|(?P<chapter>in the same chapter)|(?:vv\.|v\.|vers?\.|verses?\b)\s?(?P<verses>\d{1,3}(?:\s?[-–]\s?\d{1,3})?(?:,\s?\d{1,3}(?:\s?[-–]\s?\d{1,3})?)*))").unwrap();
+    }
+    let line_index = LineIndex::new(text);
+    let mut resolved = Vec::new();
+    let mut replaced = Vec::new();
+    let mut next = 0;
+    let mut last: Option<ScriptureCitation> = None;
+    for captures in RELATIVE_REGEX.captures_iter(text) {
+        let mat = captures.get(0).unwrap();
+        let overlapping: Vec<usize> = (0..found.len())
+            .filter(|i| found[*i].span.start < mat.end() && found[*i].span.end > mat.start())
+            .collect();
+        while next < found.len() && found[next].span.start < mat.end() {
+            let previous = &found[next];
+            if let Some(citation) = previous.citations.iter().rev().find(|citation| citation.start_chap.is_some()) {
+                last = Some(citation.clone());
+            }
+            next += 1;
+        }
+        let overlaps_citation = overlapping.iter().any(|i| !found[*i].citations.is_empty());
+        let last_citation = match &last {
+            Some(citation) if !overlaps_citation => citation,
+            _ => continue,
+        };
+
+        let span = line_index.span(mat.start(), mat.end());
+        let mut scriptures = CitationList::new();
+        scriptures.scheme = last_citation.scheme;
+        let mut diagnostics = Vec::new();
+        if captures.name("ibid").is_some() {
+            scriptures.scrip_vec.push(last_citation.clone());
+        } else if captures.name("chapter").is_some() {
+            scriptures.scrip_vec.push(ScriptureCitation { scheme: last_citation.scheme, ..ScriptureCitation::new(last_citation.book, last_citation.start_chap) });
+        } else if let (Some(verses), Some(chapter)) = (captures.name("verses"), last_citation.start_chap) {
+            let address = format!("{}:{}", chapter, verses.as_str());
+            diagnostics = scriptures.insert_address(last_citation.book, &address, library);
+        }
+        for citation in scriptures.scrip_vec.iter_mut() {
+            citation.span = Some(span);
+            citation.inferred = true;
+        }
+        if let Some(citation) = scriptures.scrip_vec.last() {
+            last = Some(citation.clone());
+        }
+        replaced.extend(overlapping);
+        resolved.push(Found { source: None, page: None, position: None, text: String::from(mat.as_str()), span, citations: scriptures.scrip_vec, diagnostics });
+    }
+
+    let mut found: Vec<Found> = found.into_iter()
+        .enumerate()
+        .filter(|(i, _)| !replaced.contains(i))
+        .map(|(_, mat)| mat)
+        .collect();
+    found.append(&mut resolved);
+    found.sort_by_key(|mat| mat.span.start);
+    found
+}
+
+/// Whether the match only took an ordinary word before an address for its book
+fn is_word(mat: &Found) -> bool {
+    mat.citations.is_empty() && mat.diagnostics.iter().any(|diagnostic| match diagnostic {
+        Diagnostic::UnknownBook(name) => name.starts_with(char::is_lowercase),
+        _ => false,
+    })
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn carried(text: &str, window: usize) -> Vec<(String, bool)> {
+        let library = Library::create().unwrap();
+        let found = crate::extract_with(text, &library, Scheme::Kjv);
+        carry_books(text, found, &library, window).iter()
+            .flat_map(|mat| mat.citations.iter().map(|citation| (citation.cref(), citation.inferred)))
+            .collect()
+    }
+
+    #[test]
+    fn carry_test() {
+        let text = "Gen. 1:1 … and again in 2:3, but Rom. v. 8 and vi. 4-6.";
+        assert_eq!(carried(text, 100), vec![
+            (String::from("Genesis 1:1"), false),
+            (String::from("Genesis 2:3"), true),
+            (String::from("Romans 5:8"), false),
+            (String::from("Romans 6:4-6"), true),
+        ]);
+    }
+
+    fn resolved(text: &str) -> Vec<(String, String, bool)> {
+        let library = Library::create().unwrap();
+        resolve_relative(text, crate::extract(text), &library).iter()
+            .flat_map(|mat| mat.citations.iter().map(move |citation| (mat.text.clone(), citation.cref(), citation.inferred)))
+            .collect()
+    }
+
+    #[test]
+    fn relative_test() {
+        let text = "Of John viii. 44 it is said; compare ver. 40, and vv. 3-5, and ibid. Then Rom. 5:8 and in the same chapter, v. 12.";
+        let expected = vec![
+            ("John viii. 44", "John 8:44", false),
+            ("ver. 40", "John 8:40", true),
+            ("vv. 3-5", "John 8:3-5", true),
+            ("ibid.", "John 8:3-5", true),
+            ("Rom. 5:8", "Romans 5:8", false),
+            ("in the same chapter", "Romans 5", true),
+            ("v. 12", "Romans 5:12", true),
+        ];
+        let expected: Vec<(String, String, bool)> = expected.into_iter()
+            .map(|(text, cref, inferred)| (String::from(text), String::from(cref), inferred))
+            .collect();
+        assert_eq!(resolved(text), expected);
+    }
+
+    #[test]
+    fn carry_and_relative_test() {
+        let library = Library::create().unwrap();
+        let text = "Of John viii. 44 it is said; compare v. 40 and vv. 3-5, and in 9:2.";
+        let found = carry_books(text, crate::extract(text), &library, 100);
+        let found = resolve_relative(text, found, &library);
+        let crefs: Vec<(&str, String)> = found.iter()
+            .flat_map(|mat| mat.citations.iter().map(move |citation| (mat.text.as_str(), citation.cref())))
+            .collect();
+        assert_eq!(crefs, vec![
+            ("John viii. 44", String::from("John 8:44")),
+            ("v. 40", String::from("John 8:40")),
+            ("vv. 3-5", String::from("John 8:3-5")),
+            ("9:2", String::from("John 9:2")),
+        ]);
+    }
+
+    #[test]
+    fn relative_without_citation_test() {
+        assert!(resolved("as in ver. 4 and ibid.").is_empty());
+        let library = Library::create().unwrap();
+        let text = "Rev. xii. 3, and ver. 40";
+        let found = resolve_relative(text, crate::extract(text), &library);
+        assert!(found[1].citations.is_empty());
+        assert!(!found[1].diagnostics.is_empty());
+    }
+
+    #[test]
+    fn window_test() {
+        let text = "Gen. 1:1, and much later 2:3";
+        assert_eq!(carried(text, 10).len(), 1);
+        assert_eq!(carried(text, 20).len(), 2);
+
+        let library = Library::create().unwrap();
+        let found = carry_books(text, crate::extract_with(text, &library, Scheme::Kjv), &library, 20);
+        let texts: Vec<&str> = found.iter().map(|mat| mat.text.as_str()).collect();
+        assert_eq!(texts, vec!["Gen. 1:1", "2:3"]);
+
+        let unknown = carried("Gen. 1:1 and Text 3:4 and 5:6", 100);
+        assert_eq!(unknown, vec![(String::from("Genesis 1:1"), false)]);
+        assert!(carried("before any book 2:3", 100).is_empty());
+    }
+}
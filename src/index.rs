@@ -0,0 +1,321 @@
+//! Building a back-of-book index of scripture references
+//!
+//! The text is split into pages, either by form feeds or by a page marker, and every
+//! citation is listed under its book and chapter in canonical order, with the pages it was
+//! found on.
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt;
+use std::io::Write;
+use std::str::FromStr;
+use regex::Regex;
+
+use crate::citation::address::ScriptureCitation;
+use crate::citation::book_id::BookId;
+use crate::Found;
+
+/// The formats that the index can be written in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum IndexFormat {
+    /// Each book on its own line, followed by its indented chapters and entries
+    #[default]
+    Text,
+    /// A heading for each book and chapter, followed by a list of its entries
+    Markdown,
+    /// `\indexentry` lines with canonical sort keys and a level for each book and chapter,
+    /// ready for makeindex
+    Latex,
+}
+
+impl fmt::Display for IndexFormat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            IndexFormat::Text => "text",
+            IndexFormat::Markdown => "markdown",
+            IndexFormat::Latex => "latex",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for IndexFormat {
+    type Err = String;
+
+    fn from_str(format: &str) -> Result<IndexFormat, String> {
+        match format.to_lowercase().as_str() {
+            "text" | "txt" => Ok(IndexFormat::Text),
+            "markdown" | "md" => Ok(IndexFormat::Markdown),
+            "latex" | "tex" => Ok(IndexFormat::Latex),
+            _ => Err(format!("unknown index format: {}", format)),
+        }
+    }
+}
+
+/// Where each page of a text starts, and its page number.
+pub struct Pages {
+    starts: Vec<(usize, String)>,
+}
+
+impl Pages {
+    /// Splits the text into pages at every form feed. The first page is page 1.
+    pub fn form_feeds(text: &str) -> Pages {
+        let mut starts = vec![(0, String::from("1"))];
+        for (i, (offset, _)) in text.match_indices('\u{c}').enumerate() {
+            starts.push((offset + 1, (i + 2).to_string()));
+        }
+        Pages { starts }
+    }
+
+    /// Splits the text into pages at every match of the marker, with each marker starting
+    /// a new page. If the marker has a capture group, it is used as the page number, and
+    /// the text before the first marker has no page. Otherwise the pages are counted from 1.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use regex::Regex;
+    /// use scripcit::index::Pages;
+    ///
+    /// let text = "[p. 11] see Gen. 1:1 [p. 12] and Rom. 3:23";
+    /// let pages = Pages::markers(text, &Regex::new(r"\[p\. (\d+)\]").unwrap());
+    /// assert_eq!(pages.page(text.find("Rom").unwrap()), Some("12"));
+    /// ```
+    pub fn markers(text: &str, marker: &Regex) -> Pages {
+        let mut starts = Vec::new();
+        if marker.captures_len() > 1 {
+            for captures in marker.captures_iter(text) {
+                let end = captures.get(0).map(|mat| mat.end()).unwrap_or(0);
+                let number = captures.get(1).map(|number| number.as_str()).unwrap_or("");
+                starts.push((end, String::from(number)));
+            }
+        } else {
+            starts.push((0, String::from("1")));
+            for (i, mat) in marker.find_iter(text).enumerate() {
+                starts.push((mat.end(), (i + 2).to_string()));
+            }
+        }
+        Pages { starts }
+    }
+
+    /// The page number of a byte offset in the text
+    pub fn page(&self, offset: usize) -> Option<&str> {
+        let after = self.starts.partition_point(|(start, _)| *start <= offset);
+        if after == 0 {
+            return None
+        }
+        Some(&self.starts[after - 1].1)
+    }
+
+    /// Sets the page of every match from where it starts
+    pub fn number(&self, found: &mut [Found]) {
+        for mat in found.iter_mut() {
+            mat.page = self.page(mat.span.start).map(String::from);
+        }
+    }
+}
+
+/// A citation in the index, with the pages it is found on in the order they appear.
+#[derive(Debug, Clone, PartialEq)]
+pub struct IndexEntry {
+    pub citation: ScriptureCitation,
+    pub pages: Vec<String>,
+}
+
+impl IndexEntry {
+    /// The citation without its book, like `3:21-24`
+    pub fn label(&self) -> String {
+        let label = self.citation.cref();
+        let label = label.trim_start_matches(self.citation.book.name()).trim();
+        if label.is_empty() {
+            String::from("whole book")
+        } else {
+            String::from(label)
+        }
+    }
+
+    /// A key that sorts the entries of a book in order, like `003.021`
+    fn sort_key(&self) -> String {
+        format!("{:03}.{:03}", self.citation.start_chap.unwrap_or(0), self.citation.start_verse.unwrap_or(0))
+    }
+}
+
+/// The index of every citation of a text, grouped by book in canonical order.
+#[derive(Debug, Clone, Default)]
+pub struct ScriptureIndex {
+    pub books: BTreeMap<BookId, Vec<IndexEntry>>,
+}
+
+impl ScriptureIndex {
+    /// Collects the citations of every match with the page they were found on. The same
+    /// citation found more than once becomes one entry with all of its pages.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scripcit::index::{Pages, ScriptureIndex};
+    ///
+    /// let text = "see Rom. 3:23\u{c}and Gen. 1:1\u{c}and Romans iii. 23";
+    /// let index = ScriptureIndex::new(&scripcit::extract(text), &Pages::form_feeds(text));
+    /// let romans = &index.books[&scripcit::BookId::Romans][0];
+    /// assert_eq!((romans.label(), romans.pages.clone()), (String::from("3:23"), vec![String::from("1"), String::from("3")]));
+    /// ```
+    pub fn new(found: &[Found], pages: &Pages) -> ScriptureIndex {
+        let mut found = found.to_vec();
+        pages.number(&mut found);
+        ScriptureIndex::numbered(&found)
+    }
+
+    /// Collects the citations of every match with the page already set on it, as when
+    /// the matches come from more than one text.
+    pub fn numbered(found: &[Found]) -> ScriptureIndex {
+        let mut books: BTreeMap<BookId, Vec<IndexEntry>> = BTreeMap::new();
+        for mat in found {
+            let page = mat.page.as_deref();
+            for citation in &mat.citations {
+                let entries = books.entry(citation.book).or_default();
+                let entry = match entries.iter_mut().find(|entry| entry.citation.canonical_cmp(citation).is_eq()) {
+                    Some(entry) => entry,
+                    None => {
+                        let citation = ScriptureCitation { span: None, ..citation.clone() };
+                        entries.push(IndexEntry { citation, pages: Vec::new() });
+                        entries.last_mut().unwrap()
+                    },
+                };
+                if let Some(page) = page {
+                    if !entry.pages.iter().any(|seen| seen == page) {
+                        entry.pages.push(String::from(page));
+                    }
+                }
+            }
+        }
+        for entries in books.values_mut() {
+            entries.sort_by(|a, b| a.citation.canonical_cmp(&b.citation));
+        }
+        ScriptureIndex { books }
+    }
+
+    /// Writes the index in the given format, with the entries of each book grouped by the
+    /// chapter they start in. Whole books come before the chapters.
+    pub fn write<W: Write>(&self, writer: &mut W, format: IndexFormat) -> Result<(), Box<dyn Error>> {
+        for (book, entries) in &self.books {
+            match format {
+                IndexFormat::Text => writeln!(writer, "{}", book)?,
+                IndexFormat::Markdown => writeln!(writer, "## {}\n", book)?,
+                IndexFormat::Latex => (),
+            }
+            let mut last_chapter = None;
+            for (i, entry) in entries.iter().enumerate() {
+                let chapter = entry.citation.start_chap;
+                if chapter.is_some() && chapter != last_chapter {
+                    match format {
+                        IndexFormat::Text => writeln!(writer, "  Chapter {}", chapter.unwrap_or(0))?,
+                        IndexFormat::Markdown if i > 0 => writeln!(writer, "\n### Chapter {}\n", chapter.unwrap_or(0))?,
+                        IndexFormat::Markdown => writeln!(writer, "### Chapter {}\n", chapter.unwrap_or(0))?,
+                        IndexFormat::Latex => (),
+                    }
+                    last_chapter = chapter;
+                }
+                let indent = if chapter.is_some() { "    " } else { "  " };
+                match format {
+                    IndexFormat::Text => writeln!(writer, "{}{}  {}", indent, entry.label(), entry.pages.join(", "))?,
+                    IndexFormat::Markdown => writeln!(writer, "- {}: {}", entry.label(), entry.pages.join(", "))?,
+                    IndexFormat::Latex => {
+                        let book_key = format!("{:03}@{}", book.index(), escape_makeindex(&book.to_string()));
+                        let chapter_key = match chapter {
+                            Some(chapter) => format!("{:03}@Chapter {}!", chapter, chapter),
+                            None => String::new(),
+                        };
+                        let label = escape_makeindex(&entry.label());
+                        for page in &entry.pages {
+                            writeln!(writer, "\\indexentry{{{}!{}{}@{}}}{{{}}}", book_key, chapter_key, entry.sort_key(), label, escape_makeindex(page))?;
+                        }
+                    },
+                }
+            }
+            if format == IndexFormat::Markdown {
+                writeln!(writer)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Escapes the characters that makeindex or TeX would read in an index entry: makeindex's
+/// `!`, `@`, `|` and `"` are quoted with `"`, and TeX's `{`, `}`, `%` and `#` with a backslash
+fn escape_makeindex(text: &str) -> String {
+    let mut escaped = String::new();
+    for c in text.chars() {
+        match c {
+            '!' | '@' | '|' | '"' => escaped.push('"'),
+            '{' | '}' | '%' | '#' => escaped.push('\\'),
+            _ => (),
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEXT: &str = "As in Rom. 3:23 and Gen. 1:1.\u{c}Again Romans iii. 23, with Gen. 2:4.\u{c}\u{c}Finally Gen. 1:1.";
+
+    fn written(format: IndexFormat) -> String {
+        let index = ScriptureIndex::new(&crate::extract(TEXT), &Pages::form_feeds(TEXT));
+        let mut out = Vec::new();
+        index.write(&mut out, format).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn form_feed_test() {
+        let pages = Pages::form_feeds(TEXT);
+        assert_eq!(pages.page(0), Some("1"));
+        assert_eq!(pages.page(TEXT.find("Again").unwrap()), Some("2"));
+        assert_eq!(pages.page(TEXT.find("Finally").unwrap()), Some("4"));
+    }
+
+    #[test]
+    fn marker_test() {
+        let text = "front matter --- next --- Gen. 1:1";
+        let pages = Pages::markers(text, &Regex::new("---").unwrap());
+        assert_eq!(pages.page(0), Some("1"));
+        assert_eq!(pages.page(text.find("Gen").unwrap()), Some("3"));
+
+        let numbered = Pages::markers("front [12] Gen. 1:1", &Regex::new(r"\[(\d+)\]").unwrap());
+        assert_eq!(numbered.page(0), None);
+        assert_eq!(numbered.page(12), Some("12"));
+    }
+
+    #[test]
+    fn text_index_test() {
+        assert_eq!(written(IndexFormat::Text), "Genesis\n  Chapter 1\n    1:1  1, 4\n  Chapter 2\n    2:4  2\nRomans\n  Chapter 3\n    3:23  1, 2\n");
+    }
+
+    #[test]
+    fn markdown_index_test() {
+        assert_eq!(written(IndexFormat::Markdown), 
+            "## Genesis\n\n### Chapter 1\n\n- 1:1: 1, 4\n\n### Chapter 2\n\n- 2:4: 2\n\n## Romans\n\n### Chapter 3\n\n- 3:23: 1, 2\n\n");
+    }
+
+    #[test]
+    fn latex_index_test() {
+        let out = written(IndexFormat::Latex);
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(lines[0], "\\indexentry{000@Genesis!001@Chapter 1!001.001@1:1}{1}");
+        assert_eq!(lines[1], "\\indexentry{000@Genesis!001@Chapter 1!001.001@1:1}{4}");
+        assert_eq!(lines[4], "\\indexentry{059@Romans!003@Chapter 3!003.023@3:23}{2}");
+    }
+
+    #[test]
+    fn latex_escape_test() {
+        let text = "[p. {x}!] see Gen. 1:1";
+        let found = crate::extract(text);
+        let index = ScriptureIndex::new(&found, &Pages::markers(text, &Regex::new(r"\[p\. (.+?)\]").unwrap()));
+        let mut out = Vec::new();
+        index.write(&mut out, IndexFormat::Latex).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "\\indexentry{000@Genesis!001@Chapter 1!001.001@1:1}{\\{x\\}\"!}\n");
+        assert_eq!(escape_makeindex("a|b@c#d%"), "a\"|b\"@c\\#d\\%");
+    }
+}
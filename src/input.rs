@@ -0,0 +1,548 @@
+//! Reading the text to search out of the different kinds of files
+//!
+//! Plain text files are read as they are. PDFs have their text extracted page by page,
+//! and the pages are joined with form feeds, so that every offset in the text can be
+//! traced back to its page. EPUB, DOCX and ODT files are unzipped and their XML is walked
+//! paragraph by paragraph, keeping the chapter, section and paragraph each one starts at.
+//! Whole directories can be searched for the files to read.
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io::{self, Cursor, Read};
+use std::path::{Path, PathBuf};
+use globset::GlobSet;
+use regex::{Captures, Regex};
+use roxmltree::{Node, ParsingOptions};
+use serde::Serialize;
+use walkdir::WalkDir;
+use zip::ZipArchive;
+
+use crate::index::Pages;
+use crate::Found;
+
+/// The named character references of XHTML, with their code points, except for the
+/// five that XML already knows
+const ENTITIES_CSV: &str = include_str!("../data/entities.csv");
+
+/// The text of a file, whether it is split into pages by form feeds, and where each of
+/// its paragraphs starts if it came from a structured document.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Document {
+    pub text: String,
+    pub paged: bool,
+    pub positions: Vec<(usize, Position)>,
+}
+
+/// Where a paragraph is in a structured document. Paragraphs are counted from the start
+/// of their chapter, headings included.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Position {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub chapter: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub section: Option<usize>,
+    pub paragraph: usize,
+}
+
+impl fmt::Display for Position {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(chapter) = self.chapter {
+            write!(f, "chapter {}, ", chapter)?;
+        }
+        if let Some(section) = self.section {
+            write!(f, "section {}, ", section)?;
+        }
+        write!(f, "paragraph {}", self.paragraph)
+    }
+}
+
+impl Document {
+    /// A plain text document, which is paged if it has form feeds
+    pub fn plain(text: String) -> Document {
+        let paged = text.contains('\u{c}');
+        Document { text, paged, positions: Vec::new() }
+    }
+
+    /// The pages of the document, if it has any
+    pub fn pages(&self) -> Option<Pages> {
+        if self.paged {
+            Some(Pages::form_feeds(&self.text))
+        } else {
+            None
+        }
+    }
+
+    /// The position of the paragraph a byte offset in the text is in
+    pub fn position(&self, offset: usize) -> Option<Position> {
+        let after = self.positions.partition_point(|(start, _)| *start <= offset);
+        if after == 0 {
+            return None
+        }
+        Some(self.positions[after - 1].1)
+    }
+
+    /// Sets the position of every match from where it starts
+    pub fn locate(&self, found: &mut [Found]) {
+        for mat in found.iter_mut() {
+            mat.position = self.position(mat.span.start);
+        }
+    }
+}
+
+/// Reads a file by its extension. PDFs have their text extracted, EPUB, DOCX and ODT
+/// files have their paragraphs extracted, and everything else is read as plain text.
+/// Plain text with form feeds is also read as paged.
+pub fn read(path: &Path) -> Result<Document, Box<dyn Error>> {
+    let extension = path.extension()
+        .map(|extension| extension.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    match extension.as_str() {
+        "pdf" => read_pdf(&fs::read(path)?),
+        "epub" => read_epub(&fs::read(path)?),
+        "docx" => read_docx(&fs::read(path)?),
+        "odt" => read_odt(&fs::read(path)?),
+        _ => Ok(Document::plain(fs::read_to_string(path)?)),
+    }
+}
+
+/// Reads plain text from stdin
+pub fn read_stdin() -> Result<Document, Box<dyn Error>> {
+    let mut text = String::new();
+    io::stdin().read_to_string(&mut text)?;
+    Ok(Document::plain(text))
+}
+
+/// Every file under a directory, in a stable order. Only the files whose path under the
+/// directory matches one of the include globs are kept, or every file if there are none,
+/// and then the files matching one of the exclude globs are left out.
+pub fn files_in(directory: &Path, include: &GlobSet, exclude: &GlobSet) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+    let mut files = Vec::new();
+    for entry in WalkDir::new(directory).sort_by_file_name() {
+        let entry = entry?;
+        if !entry.file_type().is_file() {
+            continue
+        }
+        let relative = entry.path().strip_prefix(directory).unwrap_or_else(|_| entry.path());
+        let included = include.is_empty() || include.is_match(relative);
+        if included && !exclude.is_match(relative) {
+            files.push(entry.into_path());
+        }
+    }
+    Ok(files)
+}
+
+/// Extracts the text of every page of a PDF, with the pages joined by form feeds
+pub fn read_pdf(bytes: &[u8]) -> Result<Document, Box<dyn Error>> {
+    let pages = pdf_extract::extract_text_from_mem_by_pages(bytes)?;
+    Ok(Document { text: pages.join("\u{c}"), paged: true, positions: Vec::new() })
+}
+
+/// Extracts the paragraphs of an EPUB in reading order. Every document in the spine is
+/// a chapter, and every `<h2>` in it starts a section.
+pub fn read_epub(bytes: &[u8]) -> Result<Document, Box<dyn Error>> {
+    let mut archive = ZipArchive::new(Cursor::new(bytes))?;
+    let container = zip_entry(&mut archive, "META-INF/container.xml")?;
+    let container = parse_xml(&container)?;
+    let package_path = container.descendants()
+        .find(|node| node.has_tag_name("rootfile"))
+        .and_then(|node| node.attribute("full-path"))
+        .ok_or("the EPUB container has no rootfile")?
+        .to_string();
+    let package = zip_entry(&mut archive, &package_path)?;
+    let package = parse_xml(&package)?;
+    let directory = match package_path.rfind('/') {
+        Some(slash) => &package_path[..=slash],
+        None => "",
+    };
+
+    let mut outline = Outline::default();
+    for itemref in package.descendants().filter(|node| node.has_tag_name("itemref")) {
+        let idref = itemref.attribute("idref").unwrap_or("");
+        let href = package.descendants()
+            .find(|node| node.has_tag_name("item") && node.attribute("id") == Some(idref))
+            .and_then(|node| node.attribute("href"))
+            .ok_or_else(|| format!("the EPUB spine item {} is not in the manifest", idref))?;
+        let chapter = zip_entry(&mut archive, &epub_path(directory, href))?;
+        let chapter = numeric_entities(&chapter);
+        let chapter = parse_xml(&chapter)?;
+        outline.chapter();
+        walk(chapter.root_element(), &mut outline, &xhtml_block, &xhtml_text);
+    }
+    Ok(outline.document())
+}
+
+/// Extracts the paragraphs of a Word document. `Title` and `Heading1` paragraphs start
+/// a chapter, and `Heading2` paragraphs start a section.
+pub fn read_docx(bytes: &[u8]) -> Result<Document, Box<dyn Error>> {
+    let mut archive = ZipArchive::new(Cursor::new(bytes))?;
+    let body = zip_entry(&mut archive, "word/document.xml")?;
+    let body = parse_xml(&body)?;
+    let mut outline = Outline::default();
+    walk(body.root_element(), &mut outline, &docx_block, &docx_text);
+    Ok(outline.document())
+}
+
+/// Extracts the paragraphs of an OpenDocument text. Level 1 headings start a chapter,
+/// and level 2 headings start a section.
+pub fn read_odt(bytes: &[u8]) -> Result<Document, Box<dyn Error>> {
+    let mut archive = ZipArchive::new(Cursor::new(bytes))?;
+    let content = zip_entry(&mut archive, "content.xml")?;
+    let content = parse_xml(&content)?;
+    let mut outline = Outline::default();
+    walk(content.root_element(), &mut outline, &odt_block, &odt_text);
+    Ok(outline.document())
+}
+
+/// Resolves an href of the manifest against the directory of the package, like 
+/// `../Text/Chapter%201.xhtml#start`, into the name of its entry in the archive
+fn epub_path(directory: &str, href: &str) -> String {
+    let href = href.split('#').next().unwrap_or("");
+    let bytes = href.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = bytes.get(i + 1..i + 3)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match escaped {
+            Some(byte) if bytes[i] == b'%' => {
+                decoded.push(byte);
+                i += 3;
+            },
+            _ => {
+                decoded.push(bytes[i]);
+                i += 1;
+            },
+        }
+    }
+    let path = format!("{}{}", directory, String::from_utf8_lossy(&decoded));
+    let mut parts: Vec<&str> = Vec::new();
+    for part in path.split('/') {
+        match part {
+            "" | "." => (),
+            ".." => {
+                parts.pop();
+            },
+            _ => parts.push(part),
+        }
+    }
+    parts.join("/")
+}
+
+fn zip_entry(archive: &mut ZipArchive<Cursor<&[u8]>>, name: &str) -> Result<String, Box<dyn Error>> {
+    let mut contents = String::new();
+    archive.by_name(name)?.read_to_string(&mut contents)?;
+    Ok(contents)
+}
+
+/// Rewrites the named character references of XHTML, like `&mdash;`, as numeric ones,
+/// since an XML parser only knows the ones the document declares
+fn numeric_entities(xhtml: &str) -> String {
+    lazy_static! {
+        static ref ENTITY_REGEX: Regex = Regex::new(r"&([A-Za-z][A-Za-z0-9]*);").unwrap();
+        static ref ENTITIES: HashMap<&'static str, &'static str> = ENTITIES_CSV.lines()
+            .filter_map(|line| line.split_once(','))
+            .collect();
+    }
+    ENTITY_REGEX.replace_all(xhtml, |captures: &Captures| match ENTITIES.get(&captures[1]) {
+        Some(code) => format!("&#{};", code),
+        None => String::from(&captures[0]),
+    }).into_owned()
+}
+
+fn parse_xml(xml: &str) -> Result<roxmltree::Document<'_>, Box<dyn Error>> {
+    let options = ParsingOptions { allow_dtd: true, ..ParsingOptions::default() };
+    Ok(roxmltree::Document::parse_with_options(xml, options)?)
+}
+
+/// What an element of a document starts
+enum Block {
+    Chapter,
+    Section,
+    Paragraph,
+}
+
+/// The text of a structured document as it is built up, one paragraph per line
+#[derive(Default)]
+struct Outline {
+    text: String,
+    positions: Vec<(usize, Position)>,
+    chapter: Option<usize>,
+    section: Option<usize>,
+    paragraph: usize,
+}
+
+impl Outline {
+    fn chapter(&mut self) {
+        self.chapter = Some(self.chapter.map_or(1, |chapter| chapter + 1));
+        self.section = None;
+        self.paragraph = 0;
+    }
+
+    fn section(&mut self) {
+        self.section = Some(self.section.map_or(1, |section| section + 1));
+    }
+
+    /// Adds a paragraph on its own line. Empty paragraphs are not counted.
+    fn paragraph(&mut self, text: &str) {
+        if text.trim().is_empty() {
+            return
+        }
+        if !self.text.is_empty() {
+            self.text.push('\n');
+        }
+        self.paragraph += 1;
+        let position = Position { chapter: self.chapter, section: self.section, paragraph: self.paragraph };
+        self.positions.push((self.text.len(), position));
+        self.text.push_str(text);
+    }
+
+    fn document(self) -> Document {
+        Document { text: self.text, paged: false, positions: self.positions }
+    }
+}
+
+/// Adds every block under the node to the outline. Blocks are not looked into for
+/// more blocks.
+fn walk(node: Node, outline: &mut Outline, block: &dyn Fn(Node) -> Option<Block>, text: &dyn Fn(Node) -> String) {
+    for child in node.children().filter(|child| child.is_element()) {
+        match block(child) {
+            Some(kind) => {
+                match kind {
+                    Block::Chapter => outline.chapter(),
+                    Block::Section => outline.section(),
+                    Block::Paragraph => (),
+                }
+                outline.paragraph(&text(child));
+            },
+            None => walk(child, outline, block, text),
+        }
+    }
+}
+
+fn xhtml_block(node: Node) -> Option<Block> {
+    match node.tag_name().name() {
+        "h2" => Some(Block::Section),
+        "p" | "h1" | "h3" | "h4" | "h5" | "h6" | "li" | "dt" | "dd" | "pre" | "td" | "th" => Some(Block::Paragraph),
+        _ => None,
+    }
+}
+
+fn xhtml_text(node: Node) -> String {
+    node.descendants()
+        .filter_map(|node| match node.tag_name().name() {
+            _ if node.is_text() => node.text(),
+            "br" => Some("\n"),
+            _ => None,
+        })
+        .collect()
+}
+
+fn docx_block(node: Node) -> Option<Block> {
+    if !node.has_tag_name("p") {
+        return None
+    }
+    let style = node.children()
+        .find(|child| child.has_tag_name("pPr"))
+        .and_then(|properties| properties.children().find(|child| child.has_tag_name("pStyle")))
+        .and_then(|style| style.attributes().find(|attribute| attribute.name() == "val"))
+        .map(|attribute| attribute.value());
+    match style {
+        Some("Title") | Some("Heading1") => Some(Block::Chapter),
+        Some("Heading2") => Some(Block::Section),
+        _ => Some(Block::Paragraph),
+    }
+}
+
+fn docx_text(node: Node) -> String {
+    node.descendants()
+        .filter_map(|node| match node.tag_name().name() {
+            "t" => node.text(),
+            "tab" => Some("\t"),
+            "br" | "cr" => Some("\n"),
+            _ => None,
+        })
+        .collect()
+}
+
+fn odt_block(node: Node) -> Option<Block> {
+    match node.tag_name().name() {
+        "p" => Some(Block::Paragraph),
+        "h" => {
+            let level = node.attributes()
+                .find(|attribute| attribute.name() == "outline-level")
+                .map(|attribute| attribute.value());
+            match level {
+                Some("1") => Some(Block::Chapter),
+                Some("2") => Some(Block::Section),
+                _ => Some(Block::Paragraph),
+            }
+        },
+        _ => None,
+    }
+}
+
+fn odt_text(node: Node) -> String {
+    let mut text = String::new();
+    for node in node.descendants() {
+        match node.tag_name().name() {
+            _ if node.is_text() => text.push_str(node.text().unwrap_or("")),
+            "s" => {
+                let spaces = node.attributes()
+                    .find(|attribute| attribute.name() == "c")
+                    .and_then(|attribute| attribute.value().parse().ok())
+                    .unwrap_or(1);
+                text.push_str(&" ".repeat(spaces));
+            },
+            "tab" => text.push('\t'),
+            "line-break" => text.push('\n'),
+            _ => (),
+        }
+    }
+    text
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use zip::write::{SimpleFileOptions, ZipWriter};
+
+    const PDF: &[u8] = include_bytes!("../tests/fixtures/two_pages.pdf");
+
+    fn zipped(entries: &[(&str, &str)]) -> Vec<u8> {
+        let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+        for (name, contents) in entries {
+            zip.start_file(*name, SimpleFileOptions::default()).unwrap();
+            zip.write_all(contents.as_bytes()).unwrap();
+        }
+        zip.finish().unwrap().into_inner()
+    }
+
+    fn located(document: &Document) -> Vec<(String, String)> {
+        let mut found = crate::extract(&document.text);
+        document.locate(&mut found);
+        found.iter()
+            .map(|mat| (mat.text.clone(), mat.position.map(|position| position.to_string()).unwrap_or_default()))
+            .collect()
+    }
+
+    #[test]
+    fn pdf_test() {
+        let document = read_pdf(PDF).unwrap();
+        assert!(document.paged);
+        assert_eq!(document.text.matches('\u{c}').count(), 1);
+
+        let mut found = crate::extract(&document.text);
+        document.pages().unwrap().number(&mut found);
+        let pages: Vec<(&str, Option<&str>)> = found.iter()
+            .map(|mat| (mat.text.as_str(), mat.page.as_deref()))
+            .collect();
+        assert_eq!(pages, vec![("Rom. 3:23", Some("1")), ("Gen. 1:1", Some("2")), ("Romans iii. 23", Some("2"))]);
+    }
+
+    #[test]
+    fn text_test() {
+        let path = std::env::temp_dir().join(format!("scripcit_input_{}.txt", std::process::id()));
+        fs::write(&path, "Gen. 1:1\u{c}Rom. 3:23").unwrap();
+        let document = read(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert!(document.paged);
+        assert!(document.positions.is_empty());
+
+        let plain = Document::plain(String::from("Gen. 1:1"));
+        assert!(plain.pages().is_none());
+    }
+
+    #[test]
+    fn files_in_test() {
+        let directory = std::env::temp_dir().join(format!("scripcit_files_in_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&directory);
+        fs::create_dir_all(directory.join("b/drafts")).unwrap();
+        for name in &["a.txt", "b/c.txt", "b/d.pdf", "b/drafts/e.txt"] {
+            fs::write(directory.join(name), "").unwrap();
+        }
+        let globs = |patterns: &[&str]| {
+            let mut builder = globset::GlobSetBuilder::new();
+            for pattern in patterns {
+                builder.add(globset::Glob::new(pattern).unwrap());
+            }
+            builder.build().unwrap()
+        };
+        let names = |files: Vec<PathBuf>| -> Vec<String> {
+            files.iter().map(|file| file.strip_prefix(&directory).unwrap().to_string_lossy().replace('\\', "/")).collect()
+        };
+
+        let every = files_in(&directory, &globs(&[]), &globs(&[])).unwrap();
+        assert_eq!(names(every), vec!["a.txt", "b/c.txt", "b/d.pdf", "b/drafts/e.txt"]);
+        let text = files_in(&directory, &globs(&["*.txt"]), &globs(&["**/drafts/**"])).unwrap();
+        assert_eq!(names(text), vec!["a.txt", "b/c.txt"]);
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn epub_test() {
+        let epub = zipped(&[
+            ("mimetype", "application/epub+zip"),
+            ("META-INF/container.xml", r#"<container><rootfiles><rootfile full-path="OEBPS/content.opf"/></rootfiles></container>"#),
+            ("OEBPS/content.opf", r#"<package><manifest><item id="one" href="one.xhtml"/><item id="two" href="text/two.xhtml"/></manifest>
+                <spine><itemref idref="one"/><itemref idref="two"/></spine></package>"#),
+            ("OEBPS/one.xhtml", "<!DOCTYPE html><html><body><h1>One</h1><p>As in Rom. 3:23.</p></body></html>"),
+            ("OEBPS/text/two.xhtml", "<html><body><h1>Two</h1><p>Nothing&nbsp;here&mdash;it&rsquo;s &amp; empty.</p><h2>Later</h2><div><p>See <em>Gen. 1:1</em>.</p></div></body></html>"),
+        ]);
+        let document = read_epub(&epub).unwrap();
+        assert!(!document.paged);
+        assert!(document.text.contains("Nothing\u{a0}here\u{2014}it\u{2019}s & empty."));
+        assert_eq!(located(&document), vec![
+            (String::from("Rom. 3:23"), String::from("chapter 1, paragraph 2")),
+            (String::from("Gen. 1:1"), String::from("chapter 2, section 1, paragraph 4")),
+        ]);
+    }
+
+    #[test]
+    fn epub_path_test() {
+        let epub = zipped(&[
+            ("META-INF/container.xml", r#"<container><rootfiles><rootfile full-path="OEBPS/package/content.opf"/></rootfiles></container>"#),
+            ("OEBPS/package/content.opf", r#"<package><manifest><item id="one" href="../Text/Chapter%20One.xhtml#start"/></manifest>
+                <spine><itemref idref="one"/></spine></package>"#),
+            ("OEBPS/Text/Chapter One.xhtml", "<html><body><p>As in Rom. 3:23.</p></body></html>"),
+        ]);
+        let document = read_epub(&epub).unwrap();
+        assert_eq!(document.text, "As in Rom. 3:23.");
+        assert_eq!(epub_path("", "./text/one.xhtml"), "text/one.xhtml");
+        assert_eq!(epub_path("OEBPS/", "caf%C3%A9.xhtml#p3"), "OEBPS/café.xhtml");
+    }
+
+    #[test]
+    fn docx_test() {
+        let docx = zipped(&[("word/document.xml", r#"<w:document xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main"><w:body>
+            <w:p><w:r><w:t>Preface on Gen. 1:1</w:t></w:r></w:p>
+            <w:p><w:pPr><w:pStyle w:val="Heading1"/></w:pPr><w:r><w:t>Chapter One</w:t></w:r></w:p>
+            <w:p><w:r><w:t xml:space="preserve">As in Rom. </w:t></w:r><w:r><w:t>3:23</w:t></w:r></w:p>
+            </w:body></w:document>"#)]);
+        let document = read_docx(&docx).unwrap();
+        assert_eq!(document.text, "Preface on Gen. 1:1\nChapter One\nAs in Rom. 3:23");
+        assert_eq!(located(&document), vec![
+            (String::from("Gen. 1:1"), String::from("paragraph 1")),
+            (String::from("Rom. 3:23"), String::from("chapter 1, paragraph 2")),
+        ]);
+    }
+
+    #[test]
+    fn odt_test() {
+        let odt = zipped(&[("content.xml", r#"<office:document-content xmlns:office="urn:oasis:names:tc:opendocument:xmlns:office:1.0" xmlns:text="urn:oasis:names:tc:opendocument:xmlns:text:1.0">
+            <office:body><office:text>
+            <text:h text:outline-level="1">Chapter One</text:h>
+            <text:h text:outline-level="2">First</text:h>
+            <text:p>Nothing here.</text:p>
+            <text:h text:outline-level="2">Second</text:h>
+            <text:list><text:list-item><text:p>As in Rom.<text:s/>3:23</text:p></text:list-item></text:list>
+            </office:text></office:body></office:document-content>"#)]);
+        let document = read_odt(&odt).unwrap();
+        assert_eq!(located(&document), vec![
+            (String::from("Rom. 3:23"), String::from("chapter 1, section 2, paragraph 5")),
+        ]);
+    }
+}
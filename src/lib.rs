@@ -0,0 +1,291 @@
+//! scripcit
+//! 
+//! a small library for finding scripture citations in free text, and normalizing them 
+//! into ranges of books, chapters and verses.
+//! 
+//! # Examples
+//! 
+//! ```
+//! let found = scripcit::extract("This is evident from a comparison of 1 John iii. 12, 15");
+//! assert_eq!(found[0].text, "1 John iii. 12, 15");
+//! assert_eq!(found[0].citations[1].to_string(), "1 John 3: 15");
+//! ```
+
+#[macro_use] extern crate lazy_static;
+
+use regex::Regex;
+
+pub mod citation;
+pub mod context;
+pub mod index;
+pub mod input;
+pub mod output;
+pub mod scriptureregex;
+pub mod span;
+
+pub use citation::address::{CitationList, Diagnostic, ScriptureCitation};
+pub use citation::book_id::BookId;
+pub use citation::book_linking::Library;
+pub use citation::versification::Scheme;
+pub use output::Format;
+pub use scriptureregex::regex_creator;
+pub use span::Span;
+
+/// A possible scripture citation found in a text, with the citations it was normalized into.
+#[derive(Debug, Clone)]
+pub struct Found {
+    /// The file or other source the text was read from, if known
+    pub source: Option<String>,
+    /// The page the text was found on, if the source has pages
+    pub page: Option<String>,
+    /// The chapter, section and paragraph the text was found in, if the source has them
+    pub position: Option<input::Position>,
+    /// The text that matched the scripture regex
+    pub text: String,
+    /// Where the text was found
+    pub span: Span,
+    /// The normalized citations, empty if the book could not be matched
+    pub citations: Vec<ScriptureCitation>,
+    /// Any problems normalizing the citations
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+/// Finds every possible scripture citation in a text, and normalizes it with the 
+/// built in library of books.
+pub fn extract(text: &str) -> Vec<Found> {
+    lazy_static! {
+        static ref LIBRARY: Library = Library::create().unwrap();
+    }
+    extract_with(text, &LIBRARY, Scheme::Kjv)
+}
+
+/// Finds every possible scripture citation in a text, matching the books against the 
+/// given library and reading the numbers in the given versification scheme. Books 
+/// followed by whole chapters, like `Isaiah 53`, are only found when the book is known, 
+/// and whole books only when a word points to them, like `see Ruth`.
+///
+/// # Examples
+///
+/// ```
+/// use scripcit::{Library, Scheme};
+///
+/// let library = Library::create().unwrap();
+/// let found = scripcit::extract_with("see Ruth and Isaiah 53, but not Number 5", &library, Scheme::Kjv);
+/// let crefs: Vec<String> = found.iter().map(|mat| mat.citations[0].cref()).collect();
+/// assert_eq!(crefs, vec!["Ruth", "Isaiah 53"]);
+/// ```
+pub fn extract_with(text: &str, library: &Library, scheme: Scheme) -> Vec<Found> {
+    let mut found: Vec<Found> = find_scipture_in_text(text).into_iter()
+        .map(|(span, mat)| normalize(mat, span, library, scheme))
+        .collect();
+    let mut chapters = find_chapters(text, &found, library, scheme);
+    found.append(&mut chapters);
+    let mut books = find_books(text, &found, library, scheme);
+    found.append(&mut books);
+    found.sort_by_key(|mat| mat.span.start);
+    found
+}
+
+/// Normalizes one match of the text into its citations
+fn normalize(mat: &str, span: Span, library: &Library, scheme: Scheme) -> Found {
+    let mut scriptures = CitationList::new();
+    scriptures.scheme = scheme;
+    let diagnostics = scriptures.insert(mat, library);
+    for citation in scriptures.scrip_vec.iter_mut() {
+        citation.span = Some(span);
+    }
+    Found { source: None, page: None, position: None, text: String::from(mat), span, citations: scriptures.scrip_vec, diagnostics }
+}
+
+/// Finds the books followed by whole chapters outside of the other matches. Any capitalized
+/// word can come before a number, so only the words that are the name or abbreviation of a 
+/// book and give a citation are kept. The search goes on from the next word after the others, so 
+/// `See 1 Cor. 13` is still found.
+fn find_chapters(text: &str, found: &[Found], library: &Library, scheme: Scheme) -> Vec<Found> {
+    lazy_static! {
+        static ref CHAPTERS_REGEX: Regex = Regex::new(&scriptureregex::chapters_regex_creator()).unwrap();
+    }
+    let line_index = span::LineIndex::new(text);
+    let mut chapters = Vec::new();
+    let mut start = 0;
+    while let Some(captures) = CHAPTERS_REGEX.captures_at(text, start) {
+        let mat = captures.get(0).unwrap();
+        let overlaps = found.iter().any(|other| other.span.start < mat.end() && other.span.end > mat.start());
+        let book = captures.name("book").map(|book| book.as_str()).unwrap_or("");
+        let chapter = normalize(mat.as_str(), line_index.span(mat.start(), mat.end()), library, scheme);
+        if overlaps || library.match_book_name(book).is_empty() || chapter.citations.is_empty() {
+            start = mat.start() + mat.as_str().find(char::is_whitespace).unwrap_or(mat.len());
+            continue
+        }
+        start = mat.end();
+        chapters.push(chapter);
+    }
+    chapters
+}
+
+/// Finds the whole books named on their own outside of the other matches, like `see Ruth`. 
+/// A name of more than one word, like `Song of Songs`, is tried before its first word. 
+/// Names followed by a number are left to the other passes.
+fn find_books(text: &str, found: &[Found], library: &Library, scheme: Scheme) -> Vec<Found> {
+    lazy_static! {
+        static ref BOOKS_REGEX: Regex = Regex::new(&scriptureregex::books_regex_creator()).unwrap();
+    }
+    let line_index = span::LineIndex::new(text);
+    let mut books = Vec::new();
+    for captures in BOOKS_REGEX.captures_iter(text) {
+        let name = captures.name("book").unwrap();
+        let first_word = match name.as_str().find(" of ") {
+            Some(end) => &name.as_str()[..end],
+            None => name.as_str(),
+        };
+        let (text_of_book, book) = match [name.as_str(), first_word].iter()
+            .map(|candidate| (*candidate, library.match_book_name(candidate)))
+            .find(|(_, books)| !books.is_empty())
+        {
+            Some((candidate, books)) => (candidate, books.into_iter().next().unwrap()),
+            None => continue,
+        };
+        let (start, end) = (name.start(), name.start() + text_of_book.len());
+        let overlaps = found.iter().any(|other| other.span.start < end && other.span.end > start);
+        if overlaps || text[end..].trim_start().starts_with(|c: char| c.is_ascii_digit()) {
+            continue
+        }
+        let span = line_index.span(start, end);
+        let mut citation = ScriptureCitation::new(book, None);
+        citation.scheme = scheme;
+        citation.span = Some(span);
+        books.push(Found { source: None, page: None, position: None, text: String::from(text_of_book), span, citations: vec![citation], diagnostics: Vec::new() });
+    }
+    books
+}
+
+/// Calls the scripture regex and returns every possible citation in the text, 
+/// along with where it was found. The regex is only compiled once, and is shared by 
+/// every thread.
+pub fn find_scipture_in_text(text: &str) -> Vec<(Span, &str)> {
+    lazy_static! {
+        static ref SCRIPTURE_REGEX: Regex = Regex::new(&scriptureregex::regex_creator()).unwrap();
+    }
+    let line_index = span::LineIndex::new(text);
+    SCRIPTURE_REGEX.find_iter(text)
+        .map(|mat| (line_index.span(mat.start(), mat.end()), mat.as_str()))
+        .collect()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extract_test() {
+        let text = "This is evident from a comparison of 1 John iii. 12, 15, and of Rev. xii. 3.";
+        let found = extract(text);
+        assert_eq!(found.len(), 2);
+        assert_eq!(found[0].text, "1 John iii. 12, 15");
+        assert_eq!(found[0].citations.len(), 2);
+        assert_eq!(found[1].citations[0].book, BookId::Revelation);
+        assert_eq!(found[1].citations[0].start_chap, Some(12));
+        assert_eq!(found[1].citations[0].start_verse, Some(3));
+    }
+
+    #[test]
+    fn extract_diagnostics_test() {
+        let found = extract("the Cain passage in Gen. 4:8, but not in Exod. 50:234 or in Text 3:4");
+        assert_eq!(found.len(), 3);
+        assert!(found[0].diagnostics.is_empty());
+        assert!(found[1].citations.is_empty());
+        match &found[1].diagnostics[0] {
+            Diagnostic::ImpossibleRange { citation, .. } => assert_eq!(citation.start_chap, Some(50)),
+            other => panic!("unexpected diagnostic {:?}", other),
+        }
+        assert_eq!(found[2].diagnostics, vec![Diagnostic::UnknownBook(String::from("Text"))]);
+    }
+
+    #[test]
+    fn extract_span_test() {
+        let text = "Among these the passage
+John viii. 44 requires, above all, to be examined.";
+        let found = extract(text);
+        let span = found[0].span;
+        assert_eq!(&text[span.start..span.end], "John viii. 44");
+        assert_eq!((span.line, span.column), (2, 1));
+        assert_eq!(found[0].citations[0].span, Some(span));
+    }
+
+    #[test]
+    fn parallel_test() {
+        use rayon::prelude::*;
+        let library = Library::create().unwrap();
+        let texts: Vec<String> = (1..=50).map(|chapter| format!("see Gen. {}:1 and Ps. cxix. {}", chapter, chapter)).collect();
+        let crefs = |found: Vec<Found>| -> Vec<String> {
+            found.iter().flat_map(|mat| mat.citations.iter().map(|citation| citation.cref())).collect()
+        };
+        let sequential: Vec<Vec<String>> = texts.iter()
+            .map(|text| crefs(extract_with(text, &library, Scheme::Kjv)))
+            .collect();
+        let parallel: Vec<Vec<String>> = texts.par_iter()
+            .map(|text| crefs(extract_with(text, &library, Scheme::Kjv)))
+            .collect();
+        assert_eq!(parallel, sequential);
+        assert_eq!(parallel[49], vec!["Genesis 50:1", "Psalms 119:50"]);
+    }
+
+    #[test]
+    fn chapters_test() {
+        let text = "See 1 Cor. 13, Isaiah 53 and Psalms 120–134; 136, then Gen 1:1–2:3, but not Figure 3.";
+        let found = extract(text);
+        let crefs: Vec<String> = found.iter()
+            .flat_map(|mat| mat.citations.iter().map(|citation| citation.cref()))
+            .collect();
+        assert_eq!(crefs, vec!["1 Corinthians 13", "Isaiah 53", "Psalms 120-134", "Psalms 136", "Genesis 1:1-2:3"]);
+        assert_eq!(found[2].text, "Psalms 120–134; 136");
+        assert!(found.iter().all(|mat| !mat.citations.is_empty()));
+
+        let found = extract("as in Exod. 3; 4:5 and Ps. 23; 24");
+        let crefs: Vec<String> = found.iter()
+            .flat_map(|mat| mat.citations.iter().map(|citation| citation.cref()))
+            .collect();
+        assert_eq!(crefs, vec!["Exodus 3", "Exodus 4:5", "Psalms 23", "Psalms 24"]);
+        assert!(found.iter().all(|mat| !mat.citations.is_empty()));
+    }
+
+    #[test]
+    fn following_test() {
+        let found = extract("as in John 3:16 ff. and John 3:36f., but not John 3:16 for");
+        let texts: Vec<&str> = found.iter().map(|mat| mat.text.as_str()).collect();
+        assert_eq!(texts, vec!["John 3:16 ff.", "John 3:36f.", "John 3:16"]);
+        assert_eq!(found[0].citations[0].cref(), "John 3:16ff.");
+        assert_eq!(found[1].citations[0].cref(), "John 3:36f.");
+
+        let found = extract("See 3 John 14f. and Jude 3ff. for more.");
+        let texts: Vec<&str> = found.iter().map(|mat| mat.text.as_str()).collect();
+        assert_eq!(texts, vec!["3 John 14f.", "Jude 3ff."]);
+        assert_eq!(found[0].citations[0].start_verse, Some(14));
+        assert_eq!(found[1].citations[0].cref(), "Jude 1:3ff.");
+    }
+
+    #[test]
+    fn books_test() {
+        let text = "see Ruth and read Jude. Compare the Book of Song of Songs and see Ruth 2, \
+            but not Number 5 was drawn or Is 53 worth it?";
+        let found = extract(text);
+        let crefs: Vec<(&str, String)> = found.iter()
+            .flat_map(|mat| mat.citations.iter().map(move |citation| (mat.text.as_str(), citation.cref())))
+            .collect();
+        assert_eq!(crefs, vec![
+            ("Ruth", String::from("Ruth")),
+            ("Jude", String::from("Jude")),
+            ("Song of Songs", String::from("Song of Songs")),
+            ("Ruth 2", String::from("Ruth 2")),
+        ]);
+    }
+
+    #[test]
+    fn extract_with_scheme_test() {
+        let library = Library::create().unwrap();
+        let found = extract_with("as in Mal. 3:20", &library, Scheme::Hebrew);
+        let english = found[0].citations[0].convert(Scheme::Kjv, &library);
+        assert_eq!(english.to_string(), "Malachi 4: 2");
+    }
+}
@@ -2,17 +2,18 @@
 //! 
 //! a small command line utility for extracting scripture citations from free text
 
-#[macro_use] extern crate lazy_static;
-
-use regex::Regex;
-use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
 use std::process;
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use rayon::prelude::*;
+use regex::Regex;
 use std::error::Error;
 use structopt::StructOpt;
 
-mod scriptureregex;
-#[path = "citation/roman_numerals/lib.rs"] mod roman_numerals;
-#[path = "citation/address.rs"] mod address;
+use scripcit::{context, input, output, CitationList, Format, Found, Library, Scheme, Span};
+use scripcit::output::Color;
+use scripcit::index::{IndexFormat, Pages, ScriptureIndex};
 
 // Extract all of the Scripture Citations out of A text
 #[derive(StructOpt)]
@@ -21,30 +22,84 @@ struct Cli {
     /// Run a match on a specific citation 
     #[structopt(short, long)]
     citation: Option<String>,
-    /// The file to search in
-    filename: Option<String>,
+    /// Additional book files layered on top of the built in library
+    #[structopt(short, long, parse(from_os_str))]
+    library: Vec<PathBuf>,
+    /// The versification scheme of the text: kjv, mt, lxx or vulgate
+    #[structopt(short, long, default_value = "kjv")]
+    versification: Scheme,
+    /// Convert the citations to another versification scheme for the output
+    #[structopt(short, long)]
+    to: Option<Scheme>,
+    /// End `ff.` citations, like `John 3:16ff.`, at the last verse of their chapter 
+    /// instead of leaving them open
+    #[structopt(long)]
+    bound_following: bool,
+    /// The output format: text, json, jsonl, csv, tei or usfm
+    #[structopt(short, long, default_value = "text")]
+    format: Format,
+    /// When to colour the text format: auto (only in a terminal), always or never
+    #[structopt(long, default_value = "auto")]
+    color: Color,
+    /// Print every citation of the text once, sorted in canonical order with overlapping 
+    /// and adjacent ranges merged
+    #[structopt(short, long)]
+    merge: bool,
+    /// Print a back-of-book index of the citations with their pages: text, markdown or latex
+    #[structopt(short, long)]
+    index: Option<IndexFormat>,
+    /// A pattern that starts each page, with the page number as its first group. 
+    /// Pages are split at form feeds without it.
+    #[structopt(short, long)]
+    page_marker: Option<Regex>,
+    /// Print the text with every citation wrapped in a TEI <ref>, instead of a list of citations
+    #[structopt(short, long)]
+    annotate: bool,
+    /// Only search the files in directories whose path matches one of these globs
+    #[structopt(long, number_of_values = 1)]
+    include: Vec<String>,
+    /// Leave out the files in directories whose path matches one of these globs
+    #[structopt(long, number_of_values = 1)]
+    exclude: Vec<String>,
+    /// Give addresses without a book, like `2:3`, the book of the citation at most this 
+    /// many characters before them
+    #[structopt(short, long)]
+    window: Option<usize>,
+    /// Expand references back to the last citation, like `ver. 44`, `vv. 3-5`, `ibid.` 
+    /// and `in the same chapter`, into whole citations
+    #[structopt(long)]
+    relative: bool,
+    /// The number of files to search at once, or 0 for one per processor. The output
+    /// is in the same order either way.
+    #[structopt(short, long, default_value = "1")]
+    jobs: usize,
+    /// The files to search in: plain text, PDF, EPUB, DOCX or ODT. Directories are searched
+    /// recursively, and `-` or no file at all reads from stdin.
+    filenames: Vec<String>,
 }
 fn main() {
     // This function runs the command line arguments. Whether just 
     // testing one citation, or running through a whole text file.
     let args = Cli::from_args();
 
-    if !args.citation.is_none() {
-        let library = address::book_linking::Library::create().unwrap();
-        let mut scriptures = address::CitationList::new();
-        let citation = args.citation.unwrap();
-        println!("{}", citation);
+    if let Some(citation) = &args.citation {
+        let library = create_library(&args).unwrap_or_else(|e| {
+            println!("Application error: {}", e);
+            process::exit(1);
+        });
+        let mut scriptures = CitationList::new();
+        scriptures.scheme = args.versification;
 
-        scriptures.insert(&citation, &library);
-        for reference in scriptures.scrip_vec {
-            println!("\t{}", reference);
+        let diagnostics = scriptures.insert(citation, &library);
+        let span = Span { start: 0, end: citation.len(), line: 1, column: 1 };
+        let mut found = vec![Found { source: None, page: None, position: None, text: citation.clone(), span, citations: scriptures.scrip_vec, diagnostics }];
+        convert_found(&mut found, &args, &library);
+        if let Err(e) = print_found(found, &args, &library) {
+            println!("Application error: {}", e);
         }
         process::exit(1)
     }
 
-
-    let new_regex = scriptureregex::regex_creator();
-    println!("{}", new_regex);
     if let Err(e) = run(args) {
         println!("Application error: {}", e);
         process::exit(1);
@@ -52,30 +107,158 @@ fn main() {
 }
 
 fn run(args: Cli) -> Result<(), Box<dyn Error>> {
-    // Opens a given file, and iterates through every possible scripture match in the file
+    // Opens every given file, and iterates through every possible scripture match in the files
     // to see which matched regex patterns can be normalized into a scripture citation.
-    let filename = args.filename.unwrap();
-    let contents = fs::read_to_string(filename)?;
-    let matches = find_scipture_in_text(&contents);
-    let library = address::book_linking::Library::create().unwrap();
+    // The files are searched on a pool of threads sharing the library, and the results
+    // are collected back in the order of the files.
+    let library = create_library(&args)?;
+    let filenames = find_files(&args)?;
+    let pool = rayon::ThreadPoolBuilder::new().num_threads(args.jobs).build()?;
+    let searched: Vec<_> = pool.install(|| {
+        filenames.par_iter()
+            .map(|filename| search(filename, &args, &library).map_err(|e| e.to_string()))
+            .collect()
+    });
 
-    for mat in matches {
-        let form_mat = mat.replace("\n", " ");
-        println!("{}", form_mat);
-        let mut scriptures = address::CitationList::new();
-        scriptures.insert(mat, &library);
+    let mut found = Vec::new();
+    let mut failed = 0;
+    for (filename, result) in filenames.iter().zip(searched) {
+        match result {
+            Ok((annotated, mut matches)) => {
+                match annotated {
+                    Some(annotated) if filenames.len() > 1 => print!("{}", output::annotate_source(filename, &annotated)),
+                    Some(annotated) => print!("{}", annotated),
+                    None => (),
+                }
+                found.append(&mut matches);
+            },
+            Err(e) => {
+                eprintln!("{}: {}", filename, e);
+                failed += 1;
+            },
+        }
+    }
+    if !args.annotate {
+        if let Some(format) = args.index {
+            if filenames.len() > 1 {
+                for mat in found.iter_mut() {
+                    let source = mat.source.as_deref().unwrap_or("-");
+                    mat.page = mat.page.as_ref().map(|page| format!("{}:{}", source, page));
+                }
+            }
+            let stdout = io::stdout();
+            ScriptureIndex::numbered(&found).write(&mut stdout.lock(), format)?;
+        } else {
+            print_found(found, &args, &library)?;
+        }
+    }
+    if failed > 0 {
+        return Err(format!("{} of {} files could not be read", failed, filenames.len()).into())
+    }
+    Ok(())
+}
 
-        for reference in scriptures.scrip_vec {
-            println!("\t{}", reference);
+fn find_files(args: &Cli) -> Result<Vec<String>, Box<dyn Error>> {
+    // Lists the files to search, with `-` for stdin, walking any directories for their files
+    if args.filenames.is_empty() {
+        return Ok(vec![String::from("-")])
+    }
+    let include = glob_set(&args.include)?;
+    let exclude = glob_set(&args.exclude)?;
+    let mut filenames = Vec::new();
+    for filename in &args.filenames {
+        let path = Path::new(filename);
+        if filename != "-" && path.is_dir() {
+            for file in input::files_in(path, &include, &exclude)? {
+                filenames.push(file.to_string_lossy().into_owned());
+            }
+        } else {
+            filenames.push(filename.clone());
         }
     }
+    Ok(filenames)
+}
 
-    Ok(())
+fn glob_set(patterns: &[String]) -> Result<GlobSet, Box<dyn Error>> {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        builder.add(Glob::new(pattern)?);
+    }
+    Ok(builder.build()?)
+}
+
+fn search(filename: &str, args: &Cli, library: &Library) -> Result<(Option<String>, Vec<Found>), Box<dyn Error>> {
+    // Reads one file, or stdin for `-`, and finds its matches with their source, page and position,
+    // along with the annotated text if it is asked for
+    let document = if filename == "-" {
+        input::read_stdin()?
+    } else {
+        input::read(Path::new(filename))?
+    };
+    let contents = &document.text;
+
+    let mut found = scripcit::extract_with(contents, library, args.versification);
+    if let Some(window) = args.window {
+        found = context::carry_books(contents, found, library, window);
+    }
+    if args.relative {
+        found = context::resolve_relative(contents, found, library);
+    }
+    if filename != "-" {
+        for mat in found.iter_mut() {
+            mat.source = Some(String::from(filename));
+        }
+    }
+    let pages = match &args.page_marker {
+        Some(marker) => Some(Pages::markers(contents, marker)),
+        None if args.index.is_some() => Some(document.pages().unwrap_or_else(|| Pages::form_feeds(contents))),
+        None => document.pages(),
+    };
+    if let Some(pages) = &pages {
+        pages.number(&mut found);
+    }
+    document.locate(&mut found);
+    convert_found(&mut found, args, library);
+    let annotated = if args.annotate {
+        Some(output::annotate(contents, &found))
+    } else {
+        None
+    };
+    Ok((annotated, found))
 }
 
-fn find_scipture_in_text(text: &str) -> Vec<&str> {
-    // This function calles the scripture regex and matches on a possible text
-    let regex_string = scriptureregex::regex_creator();
-    let scripture_regex = Regex::new(&regex_string).unwrap();
-    scripture_regex.find_iter(text).map(|mat| mat.as_str()).collect()
+fn print_found(found: Vec<Found>, args: &Cli, library: &Library) -> Result<(), Box<dyn Error>> {
+    // Prints the matches in the output format, or only their citations merged together
+    let stdout = io::stdout();
+    if args.merge {
+        let mut scriptures = CitationList::new();
+        scriptures.scrip_vec = found.into_iter().flat_map(|mat| mat.citations).collect();
+        scriptures.merge();
+        return output::write_citations(&mut stdout.lock(), &scriptures.scrip_vec, args.format, library)
+    }
+    output::write_with(&mut stdout.lock(), &found, args.format, library, args.color.enabled())
+}
+
+fn convert_found(found: &mut [Found], args: &Cli, library: &Library) {
+    // Converts the citations to the output versification scheme if one was given, 
+    // and closes the open ended `ff.` citations if asked to
+    if let Some(scheme) = args.to {
+        for reference in found.iter_mut().flat_map(|mat| mat.citations.iter_mut()) {
+            *reference = reference.convert(scheme, library);
+        }
+    }
+    if args.bound_following {
+        for reference in found.iter_mut().flat_map(|mat| mat.citations.iter_mut()) {
+            *reference = reference.bounded(library);
+        }
+    }
+}
+
+fn create_library(args: &Cli) -> Result<Library, Box<dyn Error>> {
+    // Builds the built in library, and layers any user supplied book files on top of it
+    let mut library = Library::create()?;
+    for path in &args.library {
+        library.load(path)?;
+    }
+    Ok(library)
 }
@@ -0,0 +1,651 @@
+//! Writing the found citations out in the different output formats
+//!
+//! The plain text format is meant for reading in the terminal, JSON and JSON Lines
+//! are meant for feeding the citations into other programs, CSV is meant for
+//! sorting and pivoting the citations in a spreadsheet, TEI is meant for digital
+//! editions, and USFM cross references are meant for pasting into translation files.
+//! `annotate` marks up the citations in place in the original text.
+use std::error::Error;
+use std::fmt;
+use std::io::{self, IsTerminal, Write};
+use std::str::FromStr;
+use serde::Serialize;
+
+use crate::citation::address::{Diagnostic, Following, ScriptureCitation, VersePart};
+use crate::citation::book_id::BookId;
+use crate::citation::book_linking::Library;
+use crate::citation::versification::Scheme;
+use crate::input::Position;
+use crate::span::Span;
+use crate::Found;
+
+/// The formats that the found citations can be written in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Format {
+    /// Each match followed by its diagnostics and indented citations
+    #[default]
+    Text,
+    /// One JSON array of every match
+    Json,
+    /// One JSON object per match, on its own line
+    Jsonl,
+    /// One row per normalized citation
+    Csv,
+    /// A TEI `<listBibl>` with a `<bibl>` for every match
+    Tei,
+    /// A USFM `\x` cross reference for every match, with the citations as `\xt` content
+    Usfm,
+}
+
+impl fmt::Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Format::Text => "text",
+            Format::Json => "json",
+            Format::Jsonl => "jsonl",
+            Format::Csv => "csv",
+            Format::Tei => "tei",
+            Format::Usfm => "usfm",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(format: &str) -> Result<Format, String> {
+        match format.to_lowercase().as_str() {
+            "text" | "txt" => Ok(Format::Text),
+            "json" => Ok(Format::Json),
+            "jsonl" | "ndjson" => Ok(Format::Jsonl),
+            "csv" => Ok(Format::Csv),
+            "tei" | "xml" => Ok(Format::Tei),
+            "usfm" => Ok(Format::Usfm),
+            _ => Err(format!("unknown output format: {}", format)),
+        }
+    }
+}
+
+/// When the text format is written in colour.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Color {
+    /// Only when stdout is a terminal
+    #[default]
+    Auto,
+    /// Even when the output is piped into a file
+    Always,
+    /// Plain text only
+    Never,
+}
+
+impl Color {
+    /// Whether the output should be coloured, checking stdout for `Auto`
+    pub fn enabled(self) -> bool {
+        match self {
+            Color::Auto => io::stdout().is_terminal(),
+            Color::Always => true,
+            Color::Never => false,
+        }
+    }
+}
+
+impl fmt::Display for Color {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Color::Auto => "auto",
+            Color::Always => "always",
+            Color::Never => "never",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for Color {
+    type Err = String;
+
+    fn from_str(color: &str) -> Result<Color, String> {
+        match color.to_lowercase().as_str() {
+            "auto" => Ok(Color::Auto),
+            "always" => Ok(Color::Always),
+            "never" => Ok(Color::Never),
+            _ => Err(format!("unknown color setting: {}", color)),
+        }
+    }
+}
+
+/// A match as it is written out in JSON
+#[derive(Serialize)]
+struct FoundRecord<'a> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    source: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    page: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    position: Option<Position>,
+    text: &'a str,
+    span: Span,
+    citations: Vec<CitationRecord<'a>>,
+    diagnostics: Vec<String>,
+}
+
+/// A normalized citation as it is written out in JSON, with the other books it could
+/// have been if the book name was ambiguous
+#[derive(Serialize)]
+struct CitationRecord<'a> {
+    book: BookId,
+    start_chap: Option<i16>,
+    start_verse: Option<i16>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    start_part: Option<VersePart>,
+    end_chap: Option<i16>,
+    end_verse: Option<i16>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    end_part: Option<VersePart>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    following: Option<Following>,
+    scheme: Scheme,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    inferred: bool,
+    candidates: &'a [BookId],
+}
+
+impl<'a> FoundRecord<'a> {
+    fn new(found: &'a Found) -> FoundRecord<'a> {
+        let candidates = found.diagnostics.iter()
+            .find_map(|diagnostic| match diagnostic {
+                Diagnostic::AmbiguousBook { candidates, .. } => Some(candidates.as_slice()),
+                _ => None,
+            })
+            .unwrap_or(&[]);
+        FoundRecord {
+            source: found.source.as_deref(),
+            page: found.page.as_deref(),
+            position: found.position,
+            text: &found.text,
+            span: found.span,
+            citations: found.citations.iter()
+                .map(|citation| CitationRecord::new(citation, candidates))
+                .collect(),
+            diagnostics: found.diagnostics.iter().map(|diagnostic| diagnostic.to_string()).collect(),
+        }
+    }
+}
+
+impl<'a> CitationRecord<'a> {
+    fn new(citation: &'a ScriptureCitation, candidates: &'a [BookId]) -> CitationRecord<'a> {
+        CitationRecord {
+            book: citation.book,
+            start_chap: citation.start_chap,
+            start_verse: citation.start_verse,
+            start_part: citation.start_part,
+            end_chap: citation.end_chap,
+            end_verse: citation.end_verse,
+            end_part: citation.end_part,
+            following: citation.following,
+            scheme: citation.scheme,
+            inferred: citation.inferred,
+            candidates,
+        }
+    }
+}
+
+/// A normalized citation as it is written out in a CSV row, along with the match
+/// it was found in
+#[derive(Serialize)]
+struct CsvRow<'a> {
+    source: Option<&'a str>,
+    page: Option<&'a str>,
+    position: Option<String>,
+    text: &'a str,
+    start: usize,
+    end: usize,
+    line: usize,
+    column: usize,
+    book: BookId,
+    start_chap: Option<i16>,
+    start_verse: Option<i16>,
+    start_part: Option<VersePart>,
+    end_chap: Option<i16>,
+    end_verse: Option<i16>,
+    end_part: Option<VersePart>,
+    following: Option<Following>,
+    scheme: Scheme,
+    inferred: bool,
+}
+
+/// Writes every match in the given format. The library is used for the book codes of
+/// the formats that need them.
+///
+/// # Examples
+///
+/// ```
+/// use scripcit::Library;
+/// use scripcit::output::{self, Format};
+///
+/// let found = scripcit::extract("see Rev. xii. 3");
+/// let mut out = Vec::new();
+/// output::write(&mut out, &found, Format::Jsonl, &Library::create().unwrap()).unwrap();
+/// assert!(String::from_utf8(out).unwrap().contains(r#""book":"Revelation""#));
+/// ```
+pub fn write<W: Write>(writer: &mut W, found: &[Found], format: Format, library: &Library) -> Result<(), Box<dyn Error>> {
+    write_with(writer, found, format, library, false)
+}
+
+/// Writes the matches like `write`, with the sources and diagnostics of the text format 
+/// in colour if `color` is set.
+pub fn write_with<W: Write>(writer: &mut W, found: &[Found], format: Format, library: &Library, color: bool) -> Result<(), Box<dyn Error>> {
+    match format {
+        Format::Text => write_text(writer, found, color),
+        Format::Json => write_json(writer, found),
+        Format::Jsonl => write_jsonl(writer, found),
+        Format::Csv => write_csv(writer, found),
+        Format::Tei => write_tei(writer, found),
+        Format::Usfm => write_usfm(writer, found, library),
+    }
+}
+
+/// Writes each match with its newlines removed and its position if it has one, then
+/// its diagnostics and its citations. The source is written before its first match, in bold
+/// if `color` is set.
+fn write_text<W: Write>(writer: &mut W, found: &[Found], color: bool) -> Result<(), Box<dyn Error>> {
+    let mut last_source = None;
+    for mat in found {
+        if mat.source.is_some() && mat.source != last_source {
+            writeln!(writer, "{}", paint(mat.source.as_deref().unwrap_or(""), "1", color))?;
+            last_source = mat.source.clone();
+        }
+        match &mat.position {
+            Some(position) => writeln!(writer, "{} ({})", mat.text.replace("\n", " "), position)?,
+            None => writeln!(writer, "{}", mat.text.replace("\n", " "))?,
+        }
+        write_diagnostics(writer, &mat.diagnostics, color)?;
+        for citation in &mat.citations {
+            if citation.inferred {
+                writeln!(writer, "\t{} (inferred)", citation)?;
+            } else {
+                writeln!(writer, "\t{}", citation)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Writes the problems found while reading a citation. With `color` set ambiguous books 
+/// are yellow like a warning and everything else is red like an error.
+pub fn write_diagnostics<W: Write>(writer: &mut W, diagnostics: &[Diagnostic], color: bool) -> Result<(), Box<dyn Error>> {
+    for diagnostic in diagnostics {
+        let code = match diagnostic {
+            Diagnostic::AmbiguousBook { .. } => "93",
+            _ => "91",
+        };
+        writeln!(writer, "{}", paint(&diagnostic.to_string(), code, color))?;
+    }
+    Ok(())
+}
+
+/// Wraps the text in an ANSI colour code when `color` is set
+fn paint(text: &str, code: &str, color: bool) -> String {
+    if color {
+        format!("\x1b[{}m{}\x1b[0m", code, text)
+    } else {
+        String::from(text)
+    }
+}
+
+fn write_json<W: Write>(writer: &mut W, found: &[Found]) -> Result<(), Box<dyn Error>> {
+    let records: Vec<FoundRecord> = found.iter().map(FoundRecord::new).collect();
+    serde_json::to_writer_pretty(&mut *writer, &records)?;
+    writeln!(writer)?;
+    Ok(())
+}
+
+fn write_jsonl<W: Write>(writer: &mut W, found: &[Found]) -> Result<(), Box<dyn Error>> {
+    for mat in found {
+        serde_json::to_writer(&mut *writer, &FoundRecord::new(mat))?;
+        writeln!(writer)?;
+    }
+    Ok(())
+}
+
+/// Writes a header, then one row for every citation. Matches that could not be
+/// normalized have no row.
+fn write_csv<W: Write>(writer: &mut W, found: &[Found]) -> Result<(), Box<dyn Error>> {
+    let mut csv_writer = csv::Writer::from_writer(writer);
+    for mat in found {
+        for citation in &mat.citations {
+            csv_writer.serialize(CsvRow {
+                source: mat.source.as_deref(),
+                page: mat.page.as_deref(),
+                position: mat.position.map(|position| position.to_string()),
+                text: &mat.text,
+                start: mat.span.start,
+                end: mat.span.end,
+                line: mat.span.line,
+                column: mat.span.column,
+                book: citation.book,
+                start_chap: citation.start_chap,
+                start_verse: citation.start_verse,
+                start_part: citation.start_part,
+                end_chap: citation.end_chap,
+                end_verse: citation.end_verse,
+                end_part: citation.end_part,
+                following: citation.following,
+                scheme: citation.scheme,
+                inferred: citation.inferred,
+            })?;
+        }
+    }
+    csv_writer.flush()?;
+    Ok(())
+}
+
+/// Writes a `<bibl>` for every match that was normalized, holding the matched text
+/// and a `<ref>` for each of its citations
+fn write_tei<W: Write>(writer: &mut W, found: &[Found]) -> Result<(), Box<dyn Error>> {
+    writeln!(writer, "<listBibl>")?;
+    for mat in found.iter().filter(|mat| !mat.citations.is_empty()) {
+        let source = match &mat.source {
+            Some(source) => format!(" source=\"{}\"", escape_xml(source)),
+            None => String::new(),
+        };
+        writeln!(writer, "  <bibl{} n=\"{}:{}\">", source, mat.span.line, mat.span.column)?;
+        writeln!(writer, "    <quote>{}</quote>", escape_xml(&mat.text))?;
+        for citation in &mat.citations {
+            let kind = if citation.inferred { " type=\"inferred\"" } else { "" };
+            writeln!(writer, "    <ref cRef=\"{}\"{}>{}</ref>", escape_xml(&citation.cref()), kind, escape_xml(&citation.to_string()))?;
+        }
+        writeln!(writer, "  </bibl>")?;
+    }
+    writeln!(writer, "</listBibl>")?;
+    Ok(())
+}
+
+/// Writes a `\x` cross reference for every match that was normalized, with its citations
+/// separated by semicolons. Books without a USFM code keep their canonical name. The source
+/// is written as a `\rem` before its first cross reference.
+fn write_usfm<W: Write>(writer: &mut W, found: &[Found], library: &Library) -> Result<(), Box<dyn Error>> {
+    let mut last_source = None;
+    for mat in found.iter().filter(|mat| !mat.citations.is_empty()) {
+        if mat.source.is_some() && mat.source != last_source {
+            writeln!(writer, "\\rem {}", mat.source.as_deref().unwrap_or(""))?;
+            last_source = mat.source.clone();
+        }
+        let references: Vec<String> = mat.citations.iter()
+            .map(|citation| citation.to_usfm(library).unwrap_or_else(|| citation.cref()))
+            .collect();
+        writeln!(writer, "\\x - \\xt {}\\x*", references.join("; "))?;
+    }
+    Ok(())
+}
+
+/// Writes a list of citations on their own, without the matches they were found in, as
+/// after merging the citations of a whole text. The spans and whether the citations were
+/// inferred are left out, since a merged citation can come from more than one match.
+///
+/// # Examples
+///
+/// ```
+/// use scripcit::{CitationList, Library};
+/// use scripcit::output::{self, Format};
+///
+/// let library = Library::create().unwrap();
+/// let mut scriptures = CitationList::new();
+/// scriptures.insert("Rom. 3:21-24, 25", &library);
+/// scriptures.merge();
+/// let mut out = Vec::new();
+/// output::write_citations(&mut out, &scriptures.scrip_vec, Format::Usfm, &library).unwrap();
+/// assert_eq!(String::from_utf8(out).unwrap(), "\\x - \\xt ROM 3:21-25\\x*\n");
+/// ```
+pub fn write_citations<W: Write>(writer: &mut W, citations: &[ScriptureCitation], format: Format, library: &Library) -> Result<(), Box<dyn Error>> {
+    let citations: Vec<ScriptureCitation> = citations.iter()
+        .map(|citation| ScriptureCitation { span: None, inferred: false, ..citation.clone() })
+        .collect();
+    match format {
+        Format::Text => {
+            for citation in &citations {
+                writeln!(writer, "{}", citation)?;
+            }
+        },
+        Format::Json => {
+            serde_json::to_writer_pretty(&mut *writer, &citations)?;
+            writeln!(writer)?;
+        },
+        Format::Jsonl => {
+            for citation in &citations {
+                serde_json::to_writer(&mut *writer, citation)?;
+                writeln!(writer)?;
+            }
+        },
+        Format::Csv => {
+            let mut csv_writer = csv::Writer::from_writer(writer);
+            for citation in &citations {
+                csv_writer.serialize(citation)?;
+            }
+            csv_writer.flush()?;
+        },
+        Format::Tei => {
+            writeln!(writer, "<listBibl>")?;
+            for citation in &citations {
+                writeln!(writer, "  <bibl><ref cRef=\"{}\">{}</ref></bibl>", escape_xml(&citation.cref()), escape_xml(&citation.to_string()))?;
+            }
+            writeln!(writer, "</listBibl>")?;
+        },
+        Format::Usfm if citations.is_empty() => (),
+        Format::Usfm => {
+            let references: Vec<String> = citations.iter()
+                .map(|citation| citation.to_usfm(library).unwrap_or_else(|| citation.cref()))
+                .collect();
+            writeln!(writer, "\\x - \\xt {}\\x*", references.join("; "))?;
+        },
+    }
+    Ok(())
+}
+
+/// Wraps every normalized match in the text in a TEI `<ref>`, with the `cRef` of each of 
+/// its citations separated by semicolons. The rest of the text is escaped for XML.
+///
+/// # Examples
+///
+/// ```
+/// let text = "compare Rev. xii. 3 & 4";
+/// let found = scripcit::extract(text);
+/// let annotated = scripcit::output::annotate(text, &found);
+/// assert_eq!(annotated, r#"compare <ref cRef="Revelation 12:3">Rev. xii. 3</ref> &amp; 4"#);
+/// ```
+pub fn annotate(text: &str, found: &[Found]) -> String {
+    let mut annotated = String::new();
+    let mut last = 0;
+    for mat in found.iter().filter(|mat| !mat.citations.is_empty()) {
+        let span = mat.span;
+        if span.start < last {
+            continue
+        }
+        let crefs: Vec<String> = mat.citations.iter().map(|citation| citation.cref()).collect();
+        annotated.push_str(&escape_xml(&text[last..span.start]));
+        annotated.push_str(&format!("<ref cRef=\"{}\">", escape_xml(&crefs.join("; "))));
+        annotated.push_str(&escape_xml(&text[span.start..span.end]));
+        annotated.push_str("</ref>");
+        last = span.end;
+    }
+    annotated.push_str(&escape_xml(&text[last..]));
+    annotated
+}
+
+/// Wraps the annotated text of one file in a `<div>` naming the file, so that the texts
+/// of several files can be told apart.
+///
+/// # Examples
+///
+/// ```
+/// let text = "compare Rev. xii. 3";
+/// let annotated = scripcit::output::annotate(text, &scripcit::extract(text));
+/// assert_eq!(scripcit::output::annotate_source("a.txt", &annotated),
+///     "<div source=\"a.txt\">compare <ref cRef=\"Revelation 12:3\">Rev. xii. 3</ref></div>\n");
+/// ```
+pub fn annotate_source(source: &str, annotated: &str) -> String {
+    format!("<div source=\"{}\">{}</div>\n", escape_xml(source), annotated)
+}
+
+/// Escapes the characters that cannot appear as is in XML text or attributes
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn written(text: &str, format: Format) -> String {
+        let found = crate::extract(text);
+        let mut out = Vec::new();
+        write(&mut out, &found, format, &Library::create().unwrap()).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn format_from_str_test() {
+        assert_eq!("json".parse::<Format>(), Ok(Format::Json));
+        assert_eq!("JSONL".parse::<Format>(), Ok(Format::Jsonl));
+        assert!("yaml".parse::<Format>().is_err());
+    }
+
+    #[test]
+    fn json_test() {
+        let out = written("compare 1 John iii. 12, 15\nwith Rev. xii. 3", Format::Json);
+        let value: serde_json::Value = serde_json::from_str(&out).unwrap();
+        let records = value.as_array().unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0]["text"], "1 John iii. 12, 15");
+        assert_eq!(records[0]["citations"][1]["start_verse"], 15);
+        assert_eq!(records[0]["citations"][1]["scheme"], "kjv");
+        assert_eq!(records[1]["span"]["line"], 2);
+        assert_eq!(records[1]["citations"][0]["book"], "Revelation");
+    }
+
+    #[test]
+    fn jsonl_test() {
+        let out = written("Gen. 4:8b and Text 3:4", Format::Jsonl);
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(lines.len(), 2);
+        let verse: serde_json::Value = serde_json::from_str(lines[0]).unwrap();
+        assert_eq!(verse["citations"][0]["start_part"], "b");
+        assert!(verse["citations"][0].get("end_part").is_none());
+        let unknown: serde_json::Value = serde_json::from_str(lines[1]).unwrap();
+        assert_eq!(unknown["citations"].as_array().unwrap().len(), 0);
+        assert_eq!(unknown["diagnostics"][0], "Unknown book: Text");
+    }
+
+    #[test]
+    fn candidates_test() {
+        let out = written("as in Jo. 1:1", Format::Jsonl);
+        let record: serde_json::Value = serde_json::from_str(out.trim()).unwrap();
+        let candidates = record["citations"][0]["candidates"].as_array().unwrap();
+        assert!(candidates.len() > 1);
+        assert!(candidates.contains(&serde_json::Value::from("John")));
+    }
+
+    #[test]
+    fn csv_test() {
+        let mut found = crate::extract("compare 1 John iii. 12, 15, but not Text 3:4");
+        for mat in found.iter_mut() {
+            mat.source = Some(String::from("notes.txt"));
+        }
+        let mut out = Vec::new();
+        write(&mut out, &found, Format::Csv, &Library::new()).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(lines[0], "source,page,position,text,start,end,line,column,book,start_chap,start_verse,start_part,end_chap,end_verse,end_part,following,scheme,inferred");
+        assert_eq!(lines[1], "notes.txt,,,\"1 John iii. 12, 15\",8,26,1,9,1 John,3,12,,,,,,kjv,false");
+        assert_eq!(lines[2], "notes.txt,,,\"1 John iii. 12, 15\",8,26,1,9,1 John,3,15,,,,,,kjv,false");
+        assert_eq!(lines.len(), 3);
+    }
+
+    #[test]
+    fn tei_test() {
+        let out = written("see II Sam. iv. 3-4 and Text 3:4", Format::Tei);
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(lines, vec![
+            "<listBibl>",
+            "  <bibl n=\"1:5\">",
+            "    <quote>II Sam. iv. 3-4</quote>",
+            "    <ref cRef=\"2 Samuel 4:3-4\">2 Samuel 4: 3 - 4: 4</ref>",
+            "  </bibl>",
+            "</listBibl>",
+        ]);
+    }
+
+    #[test]
+    fn usfm_test() {
+        let out = written("compare 1 John iii. 12, 15\nwith II Sam. iv. 3-4 and Text 3:4", Format::Usfm);
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(lines, vec![
+            "\\x - \\xt 1JN 3:12; 1JN 3:15\\x*",
+            "\\x - \\xt 2SA 4:3-4\\x*",
+        ]);
+    }
+
+    #[test]
+    fn sources_test() {
+        let mut found = crate::extract("Gen. 1:1");
+        let mut second = crate::extract("Rom. 3:23 and Rev. xii. 3");
+        found[0].source = Some(String::from("a.txt"));
+        for mat in second.iter_mut() {
+            mat.source = Some(String::from("b.txt"));
+        }
+        found.append(&mut second);
+        let library = Library::create().unwrap();
+
+        let mut out = Vec::new();
+        write(&mut out, &found, Format::Usfm, &library).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "\\rem a.txt\n\\x - \\xt GEN 1:1\\x*\n\\rem b.txt\n\\x - \\xt ROM 3:23\\x*\n\\x - \\xt REV 12:3\\x*\n");
+
+        let mut out = Vec::new();
+        write(&mut out, &found, Format::Text, &library).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        assert!(!out.contains('\x1b'));
+        let sources: Vec<&str> = out.lines().filter(|line| line.ends_with(".txt")).collect();
+        assert_eq!(sources, vec!["a.txt", "b.txt"]);
+
+        let mut out = Vec::new();
+        write_with(&mut out, &found, Format::Text, &library, true).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        let sources: Vec<&str> = out.lines().filter(|line| line.starts_with('\x1b')).collect();
+        assert_eq!(sources, vec!["\x1b[1ma.txt\x1b[0m", "\x1b[1mb.txt\x1b[0m"]);
+    }
+
+    #[test]
+    fn write_citations_test() {
+        let library = Library::create().unwrap();
+        let mut scriptures = crate::CitationList::new();
+        scriptures.insert("Rom. 3:25", &library);
+        scriptures.insert("Gen. 1:1", &library);
+        scriptures.insert("Rom. 3:21-24", &library);
+        scriptures.merge();
+
+        let mut out = Vec::new();
+        write_citations(&mut out, &scriptures.scrip_vec, Format::Csv, &library).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), 
+            "book,start_chap,start_verse,start_part,end_chap,end_verse,end_part,scheme,following\nGenesis,1,1,,,,,kjv,\nRomans,3,21,,3,25,,kjv,\n");
+
+        let mut out = Vec::new();
+        write_citations(&mut out, &scriptures.scrip_vec, Format::Text, &library).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "Genesis 1: 1\nRomans 3: 21 - 3: 25\n");
+
+        let mut out = Vec::new();
+        write_citations(&mut out, &[], Format::Usfm, &library).unwrap();
+        assert!(out.is_empty());
+    }
+
+    #[test]
+    fn annotate_test() {
+        let text = "<p>This is evident from 1 John iii. 12, 15, and Text 3:4.</p>";
+        let found = crate::extract(text);
+        assert_eq!(annotate(text, &found), 
+            "&lt;p&gt;This is evident from <ref cRef=\"1 John 3:12; 1 John 3:15\">1 John iii. 12, 15</ref>, and Text 3:4.&lt;/p&gt;");
+    }
+}
@@ -8,7 +8,7 @@ use itertools::Itertools;
 /// For all the options given, they are grouped together as options 
 fn regroup(options: Vec<String>) -> String {
     let mut combined_group: String = "(".to_owned();
-    let grouped: String = options.into_iter().intersperse(String::from("|")).collect();
+    let grouped: String = Itertools::intersperse(options.into_iter(), String::from("|")).collect();
     combined_group.push_str(&grouped);
     combined_group.push_str(&String::from(")"));
     combined_group
@@ -42,7 +42,7 @@ fn number_subparts() -> String {
     let mut part_group = regroup(vec_string(verse_parts));
     part_group.push_str(&maybe(vec_string(sub_parts)));
     three_digits.push_str(&maybe(vec![part_group]));
-    let range = r"(f{1,2}.?)?";
+    let range = r"(\s?f{1,2}\b\.?)?";
     three_digits.push_str(range);
 
     three_digits
@@ -55,7 +55,8 @@ pub fn regex_creator() -> String {
     let maybe = create_group(String::from("?"));
     let some = create_group(String::from("+"));
 
-    let word = String::from(r"\w+.?");
+    // the name of the book starts with a letter, so that a number is never read as a book
+    let word = String::from(r"[^\W\d_]\w*.?");
     let roman_numerals = String::from(r"[ivxlc]+");
 
     // Create book number options
@@ -77,7 +78,7 @@ pub fn regex_creator() -> String {
     // end_range for the verses
     let mut end_range = regroup(vec![String::from("-"), String::from("–")]);
     end_range.push_str(&chap_verse_num);
-    end_range.push_str(&maybe(vec![seperators.clone(), chap_verse_num.clone()]));
+    end_range.push_str(&maybe(vec![format!("{}{}", seperators, chap_verse_num)]));
 
     // Putting the book number with address for a citation
     let mut citation = book_num;
@@ -97,6 +98,64 @@ pub fn regex_creator() -> String {
     citation
 }
 
+/// Creates a string that can be compiled in regex for matching a chapter and verse 
+/// address that does not name its book, like `2:3` or `iv. 2-5`. Only colons and periods 
+/// can divide the chapter from the verse, so that lists of numbers are not matched. A lone 
+/// `v.` or `vv.` is left for the verses after it, as in `v. 40`, rather than read as chapter 5.
+pub fn address_regex_creator() -> String {
+    let maybe = create_group(String::from("?"));
+    let some = create_group(String::from("+"));
+    let roman_numerals = String::from(r"[ivxlc]+");
+    let chap_verse_num = number_subparts();
+
+    let mut address = String::from(r"\b");
+    address.push_str(&regroup(vec![String::from(r"\d{1,3}"), String::from(r"[ivxlc]*[ixlc][ivxlc]*")]));
+    address.push_str(r"\b[:.] ?");
+    address.push_str(&chap_verse_num);
+
+    let seperators = String::from(r"[:,.]");
+    let mut end_range = regroup(vec![String::from("-"), String::from("–")]);
+    end_range.push_str(&chap_verse_num);
+    end_range.push_str(&maybe(vec![format!("{}{}", seperators, chap_verse_num)]));
+    address.push_str(&maybe(vec![end_range]));
+
+    let mut additional_address = String::from(r"[:,;.\-]\s?");
+    additional_address.push_str(&regroup(vec![chap_verse_num, roman_numerals]));
+    address.push_str(&maybe(vec![some(vec![additional_address])]));
+    address
+}
+
+/// Creates a string that can be compiled in regex for matching a book followed by whole 
+/// chapters, like `Isaiah 53` or `Psalms 120–134`. Any capitalized word can come before 
+/// a number, so the `book` group has to be checked against the library.
+pub fn chapters_regex_creator() -> String {
+    let maybe = create_group(String::from("?"));
+    let book_num = regroup(vec_string(vec![r"[123]", r"I{1,3}"]));
+    let mut chapters = maybe(vec![format!(r"{}\s?", book_num)]);
+    chapters.push_str(r"[A-Z][a-z]+\.?");
+    // a verse of a book with one chapter can be followed by `f.` or `ff.`, like `3 John 14f.`
+    chapters = format!(r"\b(?P<book>{})\s\d{{1,3}}(\s?f{{1,2}}\b\.?|\b)", chapters);
+    let chapter_range = maybe(vec![String::from(r"\s?[-–]\s?\d{1,3}\b")]);
+    chapters.push_str(&chapter_range);
+    // more chapters of the same book, like `Psalms 120–134; 136`, or chapters and verses,
+    // like `Exod. 3; 4:5`
+    let verse = number_subparts();
+    let verses = format!(r"[:.]\s?{}(\s?[-–]\s?{})?(,\s?{}(\s?[-–]\s?{})?)*", verse, verse, verse, verse);
+    let more_chapters = create_group(String::from("*"));
+    chapters.push_str(&more_chapters(vec![format!(r";\s?\d{{1,3}}\b({}|{})", verses, chapter_range)]));
+    chapters
+}
+
+/// Creates a string that can be compiled in regex for matching a book named on its own after 
+/// a word that points to it, like `see Ruth` or `the Gospel of John`. The `book` group can 
+/// be a name of more than one word, like `Song of Songs`, and has to be checked against the 
+/// library.
+pub fn books_regex_creator() -> String {
+    let cues = regroup(vec_string(vec![r"see", r"cf\.", r"compare", r"read", r"the book of", r"the gospel of", r"the epistles? (?:to the|of)"]));
+    let book_num = regroup(vec_string(vec![r"[123]", r"I{1,3}"]));
+    let name = String::from(r"[A-Z][a-z]+(?:\sof(?:\sthe)?\s[A-Z][a-z]+)?");
+    format!(r"(?i:\b{})\s(?P<book>(?:{}\s?)?{})\b", cues, book_num, name)
+}
 
 #[cfg(test)]
 mod tests {
@@ -107,6 +166,44 @@ mod tests {
         assert_eq!(2 + 2, 4);
     }
 
+    #[test]
+    fn address_regex_test() {
+        let address = regex::Regex::new(&address_regex_creator()).unwrap();
+        let found: Vec<&str> = address.find_iter("again in 2:3, and iv. 2-5, but not 3, 4 or in cf. 3 or v. 4")
+            .map(|mat| mat.as_str())
+            .collect();
+        assert_eq!(found, vec!["2:3", "iv. 2-5"]);
+    }
+
+    #[test]
+    fn end_range_test() {
+        let scripture = regex::Regex::new(&regex_creator()).unwrap();
+        let found: Vec<&str> = scripture.find_iter("see Gen 1:1–2:3 and Rom. 3:27-4:2")
+            .map(|mat| mat.as_str())
+            .collect();
+        assert_eq!(found, vec!["Gen 1:1–2:3", "Rom. 3:27-4:2"]);
+    }
+
+    #[test]
+    fn chapters_regex_test() {
+        let chapters = regex::Regex::new(&chapters_regex_creator()).unwrap();
+        let found: Vec<&str> = chapters.find_iter("in 1 John 3, Isaiah 53 and Psalms 120–134; 136 on page 12")
+            .map(|mat| mat.as_str())
+            .collect();
+        assert_eq!(found, vec!["1 John 3", "Isaiah 53", "Psalms 120–134; 136"]);
+        let found: Vec<&str> = chapters.find_iter("as in Exod. 3; 4:5-6, 8.").map(|mat| mat.as_str()).collect();
+        assert_eq!(found, vec!["Exod. 3; 4:5-6, 8"]);
+    }
+
+    #[test]
+    fn books_regex_test() {
+        let books = regex::Regex::new(&books_regex_creator()).unwrap();
+        let found: Vec<&str> = books.captures_iter("see Ruth, cf. 1 John and the book of Song of Songs, but not Jude")
+            .map(|captures| captures.name("book").unwrap().as_str())
+            .collect();
+        assert_eq!(found, vec!["Ruth", "1 John", "Song of Songs"]);
+    }
+
     #[test]
     fn test_group() {
         let regex_frag = vec![String::from("a"), String::from("b"), String::from("c")];
@@ -0,0 +1,75 @@
+//! Locating matches in the source text
+//!
+//! Every match keeps its byte offsets in the text, along with the line and column it
+//! starts on, so that the original text can be linked to or annotated in place.
+use serde::Serialize;
+
+/// The location of a match in the source text. `start` and `end` are byte offsets,
+/// `line` and `column` are counted from 1, with the column counted in characters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+    pub line: usize,
+    pub column: usize,
+}
+
+/// The byte offset of the start of every line in a text, for turning byte offsets
+/// into lines and columns.
+pub struct LineIndex<'a> {
+    text: &'a str,
+    line_starts: Vec<usize>,
+}
+
+impl<'a> LineIndex<'a> {
+    /// Indexes the start of every line in the text
+    pub fn new(text: &'a str) -> LineIndex<'a> {
+        let mut line_starts = vec![0];
+        for (i, c) in text.char_indices() {
+            if c == '\n' {
+                line_starts.push(i + 1);
+            }
+        }
+        LineIndex { text, line_starts }
+    }
+
+    /// Creates the span of the bytes from start to end
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use scripcit::span::LineIndex;
+    ///
+    /// let text = "first line\nsee John viii. 44";
+    /// let span = LineIndex::new(text).span(15, 28);
+    /// assert_eq!((span.line, span.column), (2, 5));
+    /// ```
+    pub fn span(&self, start: usize, end: usize) -> Span {
+        let line = match self.line_starts.binary_search(&start) {
+            Ok(line) => line,
+            Err(line) => line - 1,
+        };
+        let column = self.text[self.line_starts[line]..start].chars().count() + 1;
+        Span { start, end, line: line + 1, column }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn span_test() {
+        let text = "Gen. 1:1\nἐν ἀρχῇ, cf. John i. 1";
+        let index = LineIndex::new(text);
+        let start = text.find("John").unwrap();
+        let span = index.span(start, text.len());
+        assert_eq!(span.line, 2);
+        assert_eq!(span.column, 14);
+        assert_eq!(&text[span.start..span.end], "John i. 1");
+
+        let first = index.span(0, 8);
+        assert_eq!((first.line, first.column), (1, 1));
+    }
+}